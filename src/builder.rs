@@ -0,0 +1,164 @@
+//! Configuration of JsyMk194 struct.
+use crate::error::{self, BuildError, ErrorDetail, UartError};
+use crate::link::Link;
+use crate::retry::RetryPolicy;
+use crate::{
+    check_address, crc_always_ok, is_crc_ok, Calibration, ChangeBitrate, CrcCheck, JsyMk194,
+    Measurement, Uart, DEFAULT_ADDRESS, DEFAULT_BAUDRATE, DEFAULT_TIMEOUT_MS,
+};
+use embedded_hal::delay::DelayNs;
+
+/// Builder of [`JsyMk194`]. Default configuration is the factory one of module:
+/// address 1, 4800 bit/s, CRC checked, 100ms timeout, no retry and no calibration.
+pub struct JsyMk194Builder<U, D>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    uart: U,
+    delay: D,
+    address: u8,
+    crc_check: bool,
+    timeout_ms: u32,
+    retry_policy: RetryPolicy,
+    baudrate: u32,
+    calibration1: Calibration,
+    calibration2: Calibration,
+}
+
+impl<U, D> JsyMk194Builder<U, D>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    /// Create a builder with default configuration.
+    pub fn new(uart: U, delay: D) -> Self {
+        Self {
+            uart,
+            delay,
+            address: DEFAULT_ADDRESS,
+            crc_check: true,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            retry_policy: RetryPolicy::default(),
+            baudrate: DEFAULT_BAUDRATE,
+            calibration1: Calibration::default(),
+            calibration2: Calibration::default(),
+        }
+    }
+
+    /// Set slave address of module (1 to 247). Default is 1.
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Enable or disable check of CRC of responses. Default is enabled.
+    pub fn with_crc_check(mut self, crc_check: bool) -> Self {
+        self.crc_check = crc_check;
+        self
+    }
+
+    /// Set time to wait whole response in addition to its transmit time,
+    /// used by methods without timeout.
+    /// Default is 100ms.
+    pub fn with_timeout_ms(mut self, timeout_ms: u32) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Set retry policy of reads and writes. Default is one attempt.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set bitrate of module, see [`ChangeBitrate`]. Default is 4800.
+    /// UART must already be configured with this bitrate.
+    pub fn with_baudrate(mut self, baudrate: u32) -> Self {
+        self.baudrate = baudrate;
+        self
+    }
+
+    /// Set calibration of first channel.
+    pub fn with_calibration1(mut self, calibration: Calibration) -> Self {
+        self.calibration1 = calibration;
+        self
+    }
+
+    /// Set calibration of second channel.
+    pub fn with_calibration2(mut self, calibration: Calibration) -> Self {
+        self.calibration2 = calibration;
+        self
+    }
+
+    /// Check configuration and create struct.
+    /// Return [`error::UartErrorKind::InvalidConfig`] if a value is not valid,
+    /// with builder to get back UART and delay.
+    pub fn build(self) -> Result<JsyMk194<U, D>, BuildError<Self>> {
+        if let Err(e) = self.check() {
+            return Err(BuildError::new(e, self));
+        }
+
+        Ok(self.into_device())
+    }
+
+    /// Return UART and delay.
+    pub fn release(self) -> (U, D) {
+        (self.uart, self.delay)
+    }
+
+    fn check(&self) -> Result<(), UartError> {
+        check_address(self.address).map_err(|e| invalid_config().with_detail(e.detail))?;
+
+        check_link_config(self.timeout_ms, self.baudrate)?;
+
+        // Gain of calibration must be finite and greater than 0
+        if !self.calibration1.is_valid() || !self.calibration2.is_valid() {
+            return Err(invalid_config());
+        }
+
+        Ok(())
+    }
+
+    /// Create struct without check of configuration.
+    pub(crate) fn into_device(self) -> JsyMk194<U, D> {
+        JsyMk194 {
+            link: Link::new(self.uart, self.delay, crc_check(self.crc_check))
+                .with_baudrate(self.baudrate),
+            address: self.address,
+            timeout_ms: self.timeout_ms,
+            retry_policy: self.retry_policy,
+            measurement: Measurement::new(self.calibration1, self.calibration2),
+        }
+    }
+}
+
+/// Check timeout and bitrate, shared by [`JsyMk194Builder`] and
+/// [`crate::bus::JsyBusBuilder`].
+pub(crate) fn check_link_config(timeout_ms: u32, baudrate: u32) -> Result<(), UartError> {
+    if timeout_ms == 0 {
+        return Err(invalid_config());
+    }
+
+    if !ChangeBitrate::ALL
+        .iter()
+        .any(|bitrate| bitrate.bitrate() == baudrate)
+    {
+        return Err(invalid_config().with_detail(ErrorDetail::Bitrate(baudrate)));
+    }
+
+    Ok(())
+}
+
+/// Return check of CRC of responses.
+pub(crate) fn crc_check(crc_check: bool) -> CrcCheck {
+    if crc_check {
+        is_crc_ok
+    } else {
+        crc_always_ok
+    }
+}
+
+pub(crate) fn invalid_config() -> UartError {
+    UartError::from(error::UartErrorKind::InvalidConfig)
+}
@@ -0,0 +1,344 @@
+//! Many JSY-MK-194 modules on same UART (RS-485 or TTL bus).
+//!
+//! Each module must have its own slave address, see
+//! [`crate::JsyMk194::change_address`] to commission them one by one.
+use crate::builder::{check_link_config, crc_check, invalid_config};
+use crate::error::{BuildError, DiscoverError, JsyError, UartError, UartErrorKind};
+use crate::link::Link;
+use crate::modbus::{MAX_ADDRESS, MIN_ADDRESS};
+use crate::retry::RetryPolicy;
+use crate::{
+    check_address, Calibration, ChangeBitrate, Measurement, Uart, DATA_REGISTER,
+    DATA_REGISTER_COUNT, DEFAULT_BAUDRATE, DEFAULT_TIMEOUT_MS, JSY_MK_194_MODEL, MODEL_REGISTER,
+};
+use embedded_hal::delay::DelayNs;
+
+/// Return true if error means no JSY-MK-194 answered at probed address:
+/// timeout, bad CRC, or response of another module or another model.
+fn is_no_answer<E>(error: &JsyError<E>) -> bool {
+    !error.is_transport()
+        && matches!(
+            error.kind(),
+            UartErrorKind::ReadInsuffisantBytes
+                | UartErrorKind::BadCrc
+                | UartErrorKind::WrongAddress
+                | UartErrorKind::WrongFunction
+                | UartErrorKind::WrongByteCount
+                | UartErrorKind::Exception(_)
+        )
+}
+
+/// Data of one module on bus
+pub struct BusDevice {
+    address: u8,
+    measurement: Measurement,
+}
+
+impl BusDevice {
+    fn new(address: u8) -> Self {
+        Self {
+            address,
+            measurement: Measurement::new(Calibration::default(), Calibration::default()),
+        }
+    }
+
+    /// Return slave address of module.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Return last measurement successfully read, `None` before first read.
+    pub fn last_measurement(&self) -> Option<Measurement> {
+        (self.measurement.sequence() > 0).then_some(self.measurement)
+    }
+}
+
+/// Module found on bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Discovered {
+    /// Slave address of module
+    pub address: u8,
+    /// Bitrate of module in bit/s
+    pub bitrate: u32,
+}
+
+/// Builder of [`JsyBus`]. Default configuration is the factory one of modules:
+/// 4800 bit/s, CRC checked, 100ms timeout and no retry.
+pub struct JsyBusBuilder<U, D, const N: usize>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    uart: U,
+    delay: D,
+    addresses: [u8; N],
+    crc_check: bool,
+    timeout_ms: u32,
+    retry_policy: RetryPolicy,
+    baudrate: u32,
+}
+
+impl<U, D, const N: usize> JsyBusBuilder<U, D, N>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    /// Create a builder with slave address of each module and default configuration.
+    pub fn new(uart: U, delay: D, addresses: [u8; N]) -> Self {
+        Self {
+            uart,
+            delay,
+            addresses,
+            crc_check: true,
+            timeout_ms: DEFAULT_TIMEOUT_MS,
+            retry_policy: RetryPolicy::default(),
+            baudrate: DEFAULT_BAUDRATE,
+        }
+    }
+
+    /// Enable or disable check of CRC of responses. Default is enabled.
+    pub fn with_crc_check(mut self, crc_check: bool) -> Self {
+        self.crc_check = crc_check;
+        self
+    }
+
+    /// Set time to wait response of each module, used by [`JsyBus::poll`].
+    /// Default is 100ms.
+    pub fn with_timeout_ms(mut self, timeout_ms: u32) -> Self {
+        self.timeout_ms = timeout_ms;
+        self
+    }
+
+    /// Set retry policy of reads. Default is one attempt.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set bitrate of modules, see [`ChangeBitrate`]. Default is 4800.
+    /// UART must already be configured with this bitrate.
+    pub fn with_baudrate(mut self, baudrate: u32) -> Self {
+        self.baudrate = baudrate;
+        self
+    }
+
+    /// Check configuration and create struct.
+    /// Return [`UartErrorKind::InvalidConfig`] if a value is not valid,
+    /// with builder to get back UART and delay.
+    pub fn build(self) -> Result<JsyBus<U, D, N>, BuildError<Self>> {
+        if let Err(e) = self.check() {
+            return Err(BuildError::new(e, self));
+        }
+
+        Ok(JsyBus {
+            link: Link::new(self.uart, self.delay, crc_check(self.crc_check))
+                .with_baudrate(self.baudrate),
+            timeout_ms: self.timeout_ms,
+            retry_policy: self.retry_policy,
+            devices: self.addresses.map(BusDevice::new),
+        })
+    }
+
+    /// Return UART and delay.
+    pub fn release(self) -> (U, D) {
+        (self.uart, self.delay)
+    }
+
+    fn check(&self) -> Result<(), UartError> {
+        for address in self.addresses {
+            check_address(address).map_err(|e| invalid_config().with_detail(e.detail))?;
+        }
+
+        check_link_config(self.timeout_ms, self.baudrate)
+    }
+}
+
+/// Struct to poll `N` modules sharing one UART
+pub struct JsyBus<U, D, const N: usize>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    link: Link<U, D>,
+    timeout_ms: u32,
+    retry_policy: RetryPolicy,
+    devices: [BusDevice; N],
+}
+
+impl<U, D, const N: usize> JsyBus<U, D, N>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    /// Create a new bus with slave address of each module and default configuration.
+    /// See [`JsyBusBuilder`] to change configuration.
+    pub fn new(
+        uart: U,
+        delay: D,
+        addresses: [u8; N],
+    ) -> Result<Self, BuildError<JsyBusBuilder<U, D, N>>> {
+        JsyBusBuilder::new(uart, delay, addresses).build()
+    }
+
+    /// Read data of all modules and wait default timeout each.
+    pub fn poll(&mut self) -> [Result<Measurement, JsyError<U::Error>>; N] {
+        self.poll_with_timeout(self.timeout_ms)
+    }
+
+    /// Read data of all modules, in order of addresses.
+    /// Return measurement of each module, last measurement of module in error is kept.
+    pub fn poll_with_timeout(
+        &mut self,
+        timeout_ms: u32,
+    ) -> [Result<Measurement, JsyError<U::Error>>; N] {
+        core::array::from_fn(|index| self.poll_device(index, timeout_ms))
+    }
+
+    /// Read data of module at `index`.
+    fn poll_device(
+        &mut self,
+        index: usize,
+        timeout_ms: u32,
+    ) -> Result<Measurement, JsyError<U::Error>> {
+        let device = &mut self.devices[index];
+
+        self.retry_policy.run(&mut self.link, |link| {
+            link.read_response(
+                device.address,
+                DATA_REGISTER,
+                DATA_REGISTER_COUNT,
+                timeout_ms,
+            )
+        })?;
+
+        device.measurement = device.measurement.next(self.link.segment_read());
+
+        Ok(device.measurement)
+    }
+
+    /// Scan addresses 1 to 247 at current bitrate of UART.
+    /// Modules answering are stored in `found`, scan stops when `found` is full.
+    /// `probe_timeout_ms` is the time to wait response for each address.
+    /// Return number of modules found. Scan is aborted if UART fails or request
+    /// is not sent, error keeps number of modules found before.
+    pub fn discover(
+        &mut self,
+        found: &mut [Discovered],
+        probe_timeout_ms: u32,
+    ) -> Result<usize, DiscoverError<U::Error>> {
+        let mut count = 0;
+
+        self.scan(found, &mut count, probe_timeout_ms)
+            .map_err(|e| DiscoverError::new(e, count))?;
+
+        Ok(count)
+    }
+
+    /// Scan addresses 1 to 247 for each bitrate of [`ChangeBitrate::ALL`].
+    /// UART is configured back with current bitrate at end, even if scan fails.
+    /// See [`Self::discover`].
+    pub fn discover_all_bitrates(
+        &mut self,
+        found: &mut [Discovered],
+        probe_timeout_ms: u32,
+    ) -> Result<usize, DiscoverError<U::Error>> {
+        let previous_baudrate = self.link.baudrate();
+        let mut count = 0;
+
+        let result = self.scan_all_bitrates(found, &mut count, probe_timeout_ms);
+        let restored = self.link.change_baudrate(previous_baudrate);
+
+        result
+            .and(restored)
+            .map_err(|e| DiscoverError::new(e, count))?;
+
+        Ok(count)
+    }
+
+    /// Probe each address at each bitrate, see [`Self::scan`].
+    fn scan_all_bitrates(
+        &mut self,
+        found: &mut [Discovered],
+        count: &mut usize,
+        probe_timeout_ms: u32,
+    ) -> Result<(), JsyError<U::Error>> {
+        for bitrate in ChangeBitrate::ALL {
+            self.link.change_baudrate(bitrate.bitrate())?;
+
+            self.scan(found, count, probe_timeout_ms)?;
+        }
+
+        Ok(())
+    }
+
+    /// Probe each address, store modules found after `count` first entries.
+    /// `count` is updated with each module found, even if scan fails after.
+    fn scan(
+        &mut self,
+        found: &mut [Discovered],
+        count: &mut usize,
+        probe_timeout_ms: u32,
+    ) -> Result<(), JsyError<U::Error>> {
+        for address in MIN_ADDRESS..=MAX_ADDRESS {
+            if *count >= found.len() {
+                break;
+            }
+
+            // Minimal read: model register
+            let is_jsy_mk_194 =
+                match self
+                    .link
+                    .read_registers(address, MODEL_REGISTER, 1, probe_timeout_ms)
+                {
+                    Ok(registers) => registers[0] == JSY_MK_194_MODEL,
+                    Err(e) if is_no_answer(&e) => false,
+                    Err(e) => return Err(e),
+                };
+
+            if is_jsy_mk_194 {
+                found[*count] = Discovered {
+                    address,
+                    bitrate: self.link.baudrate(),
+                };
+                *count += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Return all modules.
+    pub fn devices(&self) -> &[BusDevice; N] {
+        &self.devices
+    }
+
+    /// Return module with slave `address`.
+    pub fn device(&self, address: u8) -> Option<&BusDevice> {
+        self.devices.iter().find(|device| device.address == address)
+    }
+
+    /// Change bitrate of UART.
+    pub fn change_baudrate(&mut self, f: u32) -> Result<(), JsyError<U::Error>> {
+        self.link.change_baudrate(f)
+    }
+
+    /// Return bitrate of UART.
+    pub fn baudrate(&self) -> u32 {
+        self.link.baudrate()
+    }
+
+    /// Return time to wait response of each module, used by [`Self::poll`].
+    pub fn timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    #[cfg(test)]
+    pub(crate) fn get_uart(&self) -> &U {
+        &self.link.uart
+    }
+
+    #[cfg(test)]
+    pub(crate) fn uart_mut(&mut self) -> &mut U {
+        &mut self.link.uart
+    }
+}
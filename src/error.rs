@@ -1,5 +1,12 @@
 //! Error of JsyMk194 struct
-use std::fmt;
+//!
+//! Errors don't allocate: message is a static string and data of error is
+//! stored in [`ErrorDetail`].
+use crate::modbus::ExceptionCode;
+use core::fmt;
+
+/// Message of error, free text
+pub type Message = &'static str;
 
 /// Uart type of error
 #[derive(Debug, Clone, PartialEq)]
@@ -7,59 +14,382 @@ pub enum UartErrorKind {
     Read,
     ReadInsuffisantBytes,
     BadCrc,
+    WrongAddress,
+    WrongFunction,
+    WrongByteCount,
     Write,
     WriteInsuffisantBytes,
+    WriteRefused,
+    /// Module answered with a Modbus exception
+    Exception(ExceptionCode),
+    InvalidRequest,
+    /// Configuration of [`crate::builder::JsyMk194Builder`] is not valid
+    InvalidConfig,
+    BitrateNotDetected,
+    BitrateNotVerified,
     Other,
 }
 
-/// Uart error with type and message
+impl fmt::Display for UartErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UartErrorKind::Read => write!(f, "read on UART failed"),
+            UartErrorKind::ReadInsuffisantBytes => write!(f, "response is incomplete"),
+            UartErrorKind::BadCrc => write!(f, "CRC of response is not valid"),
+            UartErrorKind::WrongAddress => write!(f, "response comes from another module"),
+            UartErrorKind::WrongFunction => write!(f, "response is for another function"),
+            UartErrorKind::WrongByteCount => write!(f, "response has wrong byte count"),
+            UartErrorKind::Write => write!(f, "write on UART failed"),
+            UartErrorKind::WriteInsuffisantBytes => write!(f, "request is not completely written"),
+            UartErrorKind::WriteRefused => write!(f, "module refused write"),
+            UartErrorKind::Exception(code) => write!(f, "module answered exception: {}", code),
+            UartErrorKind::InvalidRequest => write!(f, "request is not valid"),
+            UartErrorKind::InvalidConfig => write!(f, "configuration is not valid"),
+            UartErrorKind::BitrateNotDetected => write!(f, "bitrate of module is not detected"),
+            UartErrorKind::BitrateNotVerified => write!(f, "bitrate of module is not verified"),
+            UartErrorKind::Other => write!(f, "error"),
+        }
+    }
+}
+
+/// Data of error, instead of formatted message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorDetail {
+    None,
+    /// Expected and actual size in bytes
+    Size {
+        expected: usize,
+        actual: usize,
+    },
+    /// Expected and actual slave address
+    Address {
+        expected: u8,
+        actual: u8,
+    },
+    /// Expected and actual function code
+    Function {
+        expected: u8,
+        actual: u8,
+    },
+    /// First register and number of registers of request
+    Registers {
+        start: u16,
+        count: u16,
+    },
+    /// Slave address of module
+    Slave(u8),
+    /// Bitrate in bit/s
+    Bitrate(u32),
+}
+
+impl fmt::Display for ErrorDetail {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorDetail::None => Ok(()),
+            ErrorDetail::Size { expected, actual } => {
+                write!(f, "expected {} bytes, got {} bytes", expected, actual)
+            }
+            ErrorDetail::Address { expected, actual } => {
+                write!(f, "expected address {}, got address {}", expected, actual)
+            }
+            ErrorDetail::Function { expected, actual } => write!(
+                f,
+                "expected function {:#04x}, got function {:#04x}",
+                expected, actual
+            ),
+            ErrorDetail::Registers { start, count } => {
+                write!(f, "{} registers from {:#06x}", count, start)
+            }
+            ErrorDetail::Slave(address) => write!(f, "module at address {}", address),
+            ErrorDetail::Bitrate(bitrate) => write!(f, "bitrate {}", bitrate),
+        }
+    }
+}
+
+/// Error of protocol or driver, with type and message
 #[derive(Debug, Clone)]
 pub struct UartError {
-    pub message: String,
+    pub message: Message,
     pub kind: UartErrorKind,
+    pub detail: ErrorDetail,
+    /// Number of attempts made before error, see [`crate::retry::RetryPolicy`]
+    pub attempts: u8,
 }
 
 impl fmt::Display for UartError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // TODO
-        write!(f, "Error when use UART interface. Reason: {}", self.message)
+        write!(f, "{}", self.kind)?;
+
+        if self.detail != ErrorDetail::None {
+            write!(f, ", {}", self.detail)?;
+        }
+
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+
+        if self.attempts > 1 {
+            write!(f, " (after {} attempts)", self.attempts)?;
+        }
+
+        Ok(())
     }
 }
 
 impl UartError {
-    pub fn new(kind: UartErrorKind, message: String) -> Self {
-        Self { message, kind }
+    pub fn new(kind: UartErrorKind, message: Message) -> Self {
+        Self {
+            message,
+            kind,
+            detail: ErrorDetail::None,
+            attempts: 1,
+        }
     }
 
     pub fn from(kind: UartErrorKind) -> Self {
-        Self {
-            message: String::new(),
-            kind,
+        Self::new(kind, Message::default())
+    }
+
+    pub fn other(message: Message) -> Self {
+        Self::new(UartErrorKind::Other, message)
+    }
+
+    /// Set data of error.
+    pub fn with_detail(mut self, detail: ErrorDetail) -> Self {
+        self.detail = detail;
+        self
+    }
+}
+
+impl core::error::Error for UartError {}
+
+/// Error of driver, `E` is error of [`crate::Uart`] implementation
+#[derive(Debug, Clone)]
+pub enum JsyError<E> {
+    /// UART failed, `error` gives operation (read, write or other) and attempts
+    Transport { error: UartError, source: E },
+    /// Response doesn't match request (CRC, header, exception, length),
+    /// or request, configuration or bitrate is not valid
+    Protocol(UartError),
+}
+
+impl<E> JsyError<E> {
+    /// Create error of UART read.
+    pub fn read(source: E) -> Self {
+        Self::transport(UartErrorKind::Read, source)
+    }
+
+    /// Create error of UART write.
+    pub fn write(source: E) -> Self {
+        Self::transport(UartErrorKind::Write, source)
+    }
+
+    /// Create error of UART for other operation, like change of bitrate.
+    pub fn other(source: E) -> Self {
+        Self::transport(UartErrorKind::Other, source)
+    }
+
+    fn transport(kind: UartErrorKind, source: E) -> Self {
+        JsyError::Transport {
+            error: UartError::from(kind),
+            source,
         }
     }
 
-    pub fn other(message: String) -> Self {
-        Self {
-            message,
-            kind: UartErrorKind::Other,
+    /// Return error of driver, with kind, detail and attempts.
+    pub fn error(&self) -> &UartError {
+        match self {
+            JsyError::Transport { error, .. } => error,
+            JsyError::Protocol(error) => error,
+        }
+    }
+
+    /// Return mutable error of driver.
+    pub fn error_mut(&mut self) -> &mut UartError {
+        match self {
+            JsyError::Transport { error, .. } => error,
+            JsyError::Protocol(error) => error,
+        }
+    }
+
+    /// Return type of error.
+    pub fn kind(&self) -> &UartErrorKind {
+        &self.error().kind
+    }
+
+    /// Return number of attempts made before error.
+    pub fn attempts(&self) -> u8 {
+        self.error().attempts
+    }
+
+    /// Return true if UART failed.
+    pub fn is_transport(&self) -> bool {
+        matches!(self, JsyError::Transport { .. })
+    }
+}
+
+impl<E> From<UartError> for JsyError<E> {
+    fn from(error: UartError) -> Self {
+        JsyError::Protocol(error)
+    }
+}
+
+impl<E> fmt::Display for JsyError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsyError::Transport { error, source } => write!(f, "{}: {}", error, source),
+            JsyError::Protocol(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl<E> core::error::Error for JsyError<E>
+where
+    E: core::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            JsyError::Transport { source, .. } => Some(source),
+            JsyError::Protocol(_) => None,
         }
     }
 }
 
 /// Error return by change bitrate
 #[derive(Debug, Clone)]
-pub struct ChangeBitrateError {
-    pub parent: UartError,
+pub struct ChangeBitrateError<E> {
+    pub parent: JsyError<E>,
 }
 
-impl fmt::Display for ChangeBitrateError {
+impl<E> fmt::Display for ChangeBitrateError<E>
+where
+    E: fmt::Display,
+{
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Cannot change bitrate. Parent error: {}", self.parent)
+        write!(f, "Cannot change bitrate: {}", self.parent)
     }
 }
 
-impl ChangeBitrateError {
-    pub fn new(parent: UartError) -> Self {
+impl<E> core::error::Error for ChangeBitrateError<E>
+where
+    E: core::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.parent)
+    }
+}
+
+impl<E> ChangeBitrateError<E> {
+    pub fn new(parent: JsyError<E>) -> Self {
         Self { parent }
     }
 }
+
+/// Error return by build of configuration, see [`crate::builder::JsyMk194Builder`].
+/// Builder is returned to get back UART and delay.
+pub struct BuildError<B> {
+    pub parent: UartError,
+    pub builder: B,
+}
+
+impl<B> BuildError<B> {
+    pub fn new(parent: UartError, builder: B) -> Self {
+        Self { parent, builder }
+    }
+}
+
+impl<B> fmt::Debug for BuildError<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BuildError")
+            .field("parent", &self.parent)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<B> fmt::Display for BuildError<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cannot build driver: {}", self.parent)
+    }
+}
+
+impl<B> core::error::Error for BuildError<B> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.parent)
+    }
+}
+
+/// Error return by scan of bus, see [`crate::bus::JsyBus::discover`]
+#[derive(Debug, Clone)]
+pub struct DiscoverError<E> {
+    pub parent: JsyError<E>,
+    /// Number of modules found and stored before error
+    pub found: usize,
+}
+
+impl<E> fmt::Display for DiscoverError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Cannot scan bus after {} modules found: {}",
+            self.found, self.parent
+        )
+    }
+}
+
+impl<E> core::error::Error for DiscoverError<E>
+where
+    E: core::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.parent)
+    }
+}
+
+impl<E> DiscoverError<E> {
+    pub fn new(parent: JsyError<E>, found: usize) -> Self {
+        Self { parent, found }
+    }
+}
+
+/// Error return by parse of frame, see [`crate::frame::Frame`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseError {
+    /// Frame doesn't have expected size, contains size of frame
+    WrongSize(usize),
+    BadCrc,
+    /// Frame is not a response to read, contains function of frame
+    WrongFunction(u8),
+    /// Byte count of header is not the one of data registers
+    WrongByteCount(u8),
+    /// Frame is an exception response of module
+    Exception(ExceptionCode),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::WrongSize(size) => write!(
+                f,
+                "Frame has {} bytes, but {} bytes are expected",
+                size,
+                crate::frame::FRAME_SIZE
+            ),
+            ParseError::BadCrc => write!(f, "CRC of frame is not valid"),
+            ParseError::WrongFunction(function) => {
+                write!(f, "Frame is a response to function {:#04x}", function)
+            }
+            ParseError::WrongByteCount(count) => {
+                write!(f, "Frame announces {} bytes of data", count)
+            }
+            ParseError::Exception(code) => write!(f, "Frame is an exception: {}", code),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {}
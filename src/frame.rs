@@ -0,0 +1,76 @@
+//! Decode response to read of data registers, without UART.
+//!
+//! Useful to decode frames captured on line or read by another Modbus stack.
+use crate::error::ParseError;
+use crate::modbus::{
+    is_crc_ok, ExceptionCode, CRC_SIZE, EXCEPTION_FLAG, EXCEPTION_RESPONSE_SIZE,
+    READ_HOLDING_REGISTERS,
+};
+use crate::{Calibration, Measurement, DATA_REGISTER_COUNT, READ_HEADER_SIZE};
+
+/// Size of response to read of data registers (0x0048 to 0x0055), 32 bits each
+pub const FRAME_SIZE: usize = READ_HEADER_SIZE + DATA_REGISTER_COUNT as usize * 4 + CRC_SIZE;
+
+/// Valid response to read of data registers, see crate doc for content.
+/// Bytes are borrowed, not copied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    /// Check size, CRC and header of `data`.
+    pub fn new(data: &'a [u8]) -> Result<Self, ParseError> {
+        if data.len() == EXCEPTION_RESPONSE_SIZE && data[1] & EXCEPTION_FLAG != 0 && is_crc_ok(data)
+        {
+            return Err(ParseError::Exception(ExceptionCode::from(data[2])));
+        }
+
+        if data.len() != FRAME_SIZE {
+            return Err(ParseError::WrongSize(data.len()));
+        }
+
+        if !is_crc_ok(data) {
+            return Err(ParseError::BadCrc);
+        }
+
+        if data[1] != READ_HOLDING_REGISTERS {
+            return Err(ParseError::WrongFunction(data[1]));
+        }
+
+        if data[2] as usize != FRAME_SIZE - READ_HEADER_SIZE - CRC_SIZE {
+            return Err(ParseError::WrongByteCount(data[2]));
+        }
+
+        Ok(Self { data })
+    }
+
+    /// Check and decode `data` in one call.
+    pub fn parse(data: &[u8]) -> Result<Measurement, ParseError> {
+        Ok(Frame::new(data)?.measurement())
+    }
+
+    /// Return slave address of module which sent frame.
+    pub fn address(&self) -> u8 {
+        self.data[0]
+    }
+
+    /// Return bytes of frame.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Decode measurement, without calibration. Sequence of measurement is 0.
+    pub fn measurement(&self) -> Measurement {
+        self.measurement_with_calibration(Calibration::default(), Calibration::default())
+    }
+
+    /// Decode measurement with calibration of each channel.
+    pub fn measurement_with_calibration(
+        &self,
+        calibration1: Calibration,
+        calibration2: Calibration,
+    ) -> Measurement {
+        Measurement::new(calibration1, calibration2).decode(self.data)
+    }
+}
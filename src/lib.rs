@@ -26,21 +26,72 @@
 //! |            15 | negative kwh2      | 55, 56, 57, 58 |
 //! |            16 | crc                | 59, 60         |
 //!
+//! Crate is `no_std` when default `std` feature is disabled.
+#![cfg_attr(not(feature = "std"), no_std)]
+use builder::JsyMk194Builder;
+use core::ops::Deref;
 use embedded_hal::delay::DelayNs;
-use error::UartError;
+use error::{JsyError, UartError};
+use link::Link;
+use modbus::{is_crc_ok, CRC_SIZE};
+use retry::RetryPolicy;
 
+pub mod builder;
+pub mod bus;
 pub mod error;
-#[cfg(test)]
+pub mod frame;
+mod link;
+pub mod modbus;
+pub mod retry;
+#[cfg(feature = "embedded-io")]
+pub mod serial;
+#[cfg(all(test, feature = "std"))]
 mod tests;
 
 // Maximum message to read
 const SEGMENT_READ: usize = 64;
-/// Maximum size of message to send
-const SEGMENT_WRITE: usize = 8;
-/// Size of message to read
-const READ_DATA_SIZE: usize = 61;
-/// Size of message to write change bitrate
-const SEGMENT_WRITE_CHANGE_BIT_RATE: usize = 11;
+/// Size of header of read response (address, function, byte count)
+const READ_HEADER_SIZE: usize = 3;
+/// Maximum number of 16 bits values in one response
+pub const MAX_REGISTER_VALUES: usize = (SEGMENT_READ - READ_HEADER_SIZE - CRC_SIZE) / 2;
+/// Default timeout to wait response
+const DEFAULT_TIMEOUT_MS: u32 = 100;
+/// Default bitrate of module
+const DEFAULT_BAUDRATE: u32 = 4800;
+/// Time for module to apply new bitrate before it answers at this bitrate
+const BITRATE_SETTLE_MS: u32 = 1000;
+/// Size of buffer to clear input of UART
+const CLEAR_INPUT_SIZE: usize = 16;
+/// Maximum read to clear input of UART, a bus never silent must not block driver
+const CLEAR_INPUT_MAX_READ: usize = 16;
+
+/// First register of data
+const DATA_REGISTER: u16 = 0x0048;
+/// Number of registers of data
+const DATA_REGISTER_COUNT: u16 = 0x000e;
+/// First register of system parameters (model, ranges, address and bitrate)
+const SYSTEM_REGISTER: u16 = 0x0000;
+/// Register of model
+const MODEL_REGISTER: u16 = 0x0000;
+/// Number of registers of system parameters
+const SYSTEM_REGISTER_COUNT: u16 = 0x0005;
+/// Register of slave address (high byte) and bitrate (low byte)
+const ADDRESS_BITRATE_REGISTER: u16 = 0x0004;
+/// Last register of data
+const LAST_DATA_REGISTER: u16 = 0x0055;
+/// Register to clear energy, two registers of zero must be written
+const ENERGY_RESET_REGISTER: u16 = 0x000c;
+/// Register of positive energy of first channel
+const POSITIVE_ENERGY_1_REGISTER: u16 = 0x004b;
+/// Register of negative energy of first channel
+const NEGATIVE_ENERGY_1_REGISTER: u16 = 0x004d;
+/// Offset of registers of second channel from first channel
+const CHANNEL_2_REGISTER_OFFSET: u16 = 0x0008;
+
+/// Default slave address of module
+pub const DEFAULT_ADDRESS: u8 = 0x01;
+/// Model stored in module register
+pub const JSY_MK_194_MODEL: u16 = 0x0194;
 
 /// Channel 1 offset
 const CHANNEL_1_OFFSET: usize = 3;
@@ -69,32 +120,6 @@ const POWER_SIGN_2: usize = 28;
 
 type CrcCheck = fn(&[u8]) -> bool;
 
-// From https://ctlsys.com/support/how_to_compute_the_modbus_rtu_message_crc/
-fn is_crc_ok(buf: &[u8]) -> bool {
-    let mut crc: u16 = 0xFFFF;
-    let low = buf.len() - 2;
-    let hi = buf.len() - 1;
-    let buf_crc: u16 = (buf[hi] as u16) * 256 + (buf[low] as u16);
-
-    for current_byte in buf.iter().take(buf.len() - 2) {
-        crc ^= *current_byte as u16; // XOR byte into least sig. byte of crc
-
-        for _ in (0..8).rev() {
-            // Loop over each bit
-            if (crc & 0x0001) != 0 {
-                // If the LSB is set
-                crc >>= 1; // Shift right and XOR 0xA001
-                crc ^= 0xA001;
-            } else {
-                // Else LSB is not set
-                crc >>= 1; // Just shift right
-            }
-        }
-    }
-    // Note, this number has low and high bytes swapped, so use it accordingly (or swap bytes)
-    crc == buf_crc
-}
-
 fn crc_always_ok(_buf: &[u8]) -> bool {
     true
 }
@@ -108,8 +133,61 @@ fn conv8to32(hi_byte: u8, mid_byte_2: u8, mid_byte_1: u8, lo_byte: u8) -> u32 {
         + ((hi_byte as u32) << 24)
 }
 
+/// Return size in bytes of register.
+/// On JSY-MK-194, data registers (0x0048 to 0x0055) are 32 bits wide.
+fn register_size(register: u16) -> usize {
+    if (DATA_REGISTER..=LAST_DATA_REGISTER).contains(&register) {
+        4
+    } else {
+        2
+    }
+}
+
+/// Return size of response to read `count` registers from `start`
+fn read_response_size(start: u16, count: u16) -> usize {
+    let data_size: usize = (0..count)
+        .map(|index| register_size(start.wrapping_add(index)))
+        .sum();
+
+    READ_HEADER_SIZE + data_size + CRC_SIZE
+}
+
+/// Return number of registers from `start` filled by `words` 16 bits values.
+/// `None` if last register is not filled.
+fn register_count(start: u16, words: usize) -> Option<u16> {
+    let mut count: u16 = 0;
+    let mut size = 0;
+
+    while size < words * 2 {
+        size += register_size(start.wrapping_add(count));
+        count = count.checked_add(1)?;
+    }
+
+    (size == words * 2).then_some(count)
+}
+
+/// Check address can be assigned to module
+fn check_address(address: u8) -> Result<(), UartError> {
+    if (modbus::MIN_ADDRESS..=modbus::MAX_ADDRESS).contains(&address) {
+        return Ok(());
+    }
+
+    Err(error::UartError::from(error::UartErrorKind::InvalidRequest)
+        .with_detail(error::ErrorDetail::Slave(address)))
+}
+
+/// Return true if error can be caused by a bitrate mismatch
+fn is_link_error(kind: &error::UartErrorKind) -> bool {
+    matches!(
+        kind,
+        error::UartErrorKind::Read
+            | error::UartErrorKind::ReadInsuffisantBytes
+            | error::UartErrorKind::BadCrc
+    )
+}
+
 /// Get data number X (see crate doc)
-fn get_data(segment_read: &[u8; SEGMENT_READ], n: usize) -> u32 {
+fn get_data(segment_read: &[u8], n: usize) -> u32 {
     conv8to32(
         segment_read[n],
         segment_read[n + 1],
@@ -118,19 +196,8 @@ fn get_data(segment_read: &[u8; SEGMENT_READ], n: usize) -> u32 {
     )
 }
 
-/// Get power with right sign.
-#[inline(always)]
-fn power(segment_read: &[u8; SEGMENT_READ], power: usize, sign: usize) -> f32 {
-    let p = (get_data(segment_read, power) as f32) * 0.0001;
-
-    if segment_read[sign] == 1 && p > 0.0 {
-        return -p;
-    }
-
-    p
-}
-
 /// Value to change bitrate
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ChangeBitrate {
     B4800,
     B9600,
@@ -138,28 +205,232 @@ pub enum ChangeBitrate {
     B38400,
 }
 
+impl ChangeBitrate {
+    /// All bitrates supported by module, from the slowest
+    pub const ALL: [ChangeBitrate; 4] = [
+        ChangeBitrate::B4800,
+        ChangeBitrate::B9600,
+        ChangeBitrate::B19200,
+        ChangeBitrate::B38400,
+    ];
+
+    /// Return code of bitrate stored in module register.
+    pub fn code(&self) -> u8 {
+        match self {
+            ChangeBitrate::B4800 => 0x05,
+            ChangeBitrate::B9600 => 0x06,
+            ChangeBitrate::B19200 => 0x07,
+            ChangeBitrate::B38400 => 0x08,
+        }
+    }
+
+    /// Return bitrate in bit/s.
+    pub fn bitrate(&self) -> u32 {
+        match self {
+            ChangeBitrate::B4800 => 4800,
+            ChangeBitrate::B9600 => 9600,
+            ChangeBitrate::B19200 => 19200,
+            ChangeBitrate::B38400 => 38400,
+        }
+    }
+}
+
+/// Identification and measurement range of module
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceInfo {
+    model: u16,
+    voltage_range: u16,
+    current_range: u16,
+    address: u8,
+    bitrate_code: u8,
+}
+
+impl DeviceInfo {
+    /// Build info from system registers (0x0000 to 0x0004)
+    fn from_registers(registers: &[u16]) -> Self {
+        Self {
+            model: registers[0],
+            voltage_range: registers[2],
+            current_range: registers[3],
+            address: (registers[4] >> 8) as u8,
+            bitrate_code: (registers[4] & 0x00ff) as u8,
+        }
+    }
+
+    /// Return model of module, 0x0194 for JSY-MK-194.
+    pub fn model(&self) -> u16 {
+        self.model
+    }
+
+    /// Return true if module is a JSY-MK-194.
+    pub fn is_jsy_mk_194(&self) -> bool {
+        self.model == JSY_MK_194_MODEL
+    }
+
+    /// Return voltage range in volt.
+    pub fn voltage_range(&self) -> u16 {
+        self.voltage_range
+    }
+
+    /// Return current range in A.
+    pub fn current_range(&self) -> f32 {
+        (self.current_range as f32) * 0.1
+    }
+
+    /// Return slave address stored in module.
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Return code of bitrate stored in module.
+    pub fn bitrate_code(&self) -> u8 {
+        self.bitrate_code
+    }
+
+    /// Return bitrate in bit/s, `None` if code is unknown.
+    pub fn bitrate(&self) -> Option<u32> {
+        match self.bitrate_code {
+            0x03 => Some(1200),
+            0x04 => Some(2400),
+            0x05 => Some(4800),
+            0x06 => Some(9600),
+            0x07 => Some(19200),
+            0x08 => Some(38400),
+            _ => None,
+        }
+    }
+}
+
+/// Raw 16 bits values of registers read from module.
+/// A 32 bits data register is returned as two values, high word first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Registers {
+    values: [u16; MAX_REGISTER_VALUES],
+    len: usize,
+}
+
+impl Registers {
+    /// Build values from data part of response (big endian words)
+    fn from_bytes(data: &[u8]) -> Self {
+        let mut registers = Self {
+            values: [0; MAX_REGISTER_VALUES],
+            len: data.len() / 2,
+        };
+
+        for (value, bytes) in registers.values.iter_mut().zip(data.chunks_exact(2)) {
+            *value = ((bytes[0] as u16) << 8) | bytes[1] as u16;
+        }
+
+        registers
+    }
+
+    /// Return values as slice.
+    pub fn as_slice(&self) -> &[u16] {
+        &self.values[0..self.len]
+    }
+}
+
+impl Deref for Registers {
+    type Target = [u16];
+
+    fn deref(&self) -> &[u16] {
+        self.as_slice()
+    }
+}
+
 /// Uart trait that must be impremented for specific hardware
 pub trait Uart {
-    /// Read multiple bytes into a slice
-    fn read(&mut self, buf: &mut [u8], timeout: u32) -> Result<usize, error::UartError>;
+    /// Error of hardware, returned as source of [`JsyError`]
+    type Error;
+
+    /// Read multiple bytes into a slice.
+    /// Can return only bytes available, driver reads again until frame is complete
+    /// with time left of its timeout.
+    /// Return 0 when no byte is received in `timeout` ms, this ends response.
+    fn read(&mut self, buf: &mut [u8], timeout: u32) -> Result<usize, Self::Error>;
 
     /// Write multiple bytes from a slice
-    fn write(&mut self, bytes: &[u8]) -> Result<usize, error::UartError>;
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error>;
 
     /// Allow change uart config
-    fn change_baudrate(&mut self, f: u32) -> Result<(), error::UartError> ;
+    fn change_baudrate(&mut self, f: u32) -> Result<(), Self::Error>;
+
+    /// Drop bytes received but not read, like late bytes of a previous response.
+    /// Called before each request. Default implementation reads with no timeout
+    /// until no byte is available.
+    fn clear_input(&mut self) -> Result<(), Self::Error> {
+        let mut buf = [0; CLEAR_INPUT_SIZE];
+
+        for _ in 0..CLEAR_INPUT_MAX_READ {
+            match self.read(&mut buf, 0) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => (),
+            }
+        }
+
+        Ok(())
+    }
 }
 
-/// Channel struct to get information. JSY MK 194 has 2 channels
+/// Gain applied to measures of a channel, to correct a current transformer
+/// or a voltage divider. Power and energy are corrected by both gains.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    /// Gain of voltage
+    pub voltage: f32,
+    /// Gain of current
+    pub current: f32,
+}
+
+impl Default for Calibration {
+    /// No correction.
+    fn default() -> Self {
+        Self::new(1.0, 1.0)
+    }
+}
+
+impl Calibration {
+    /// Create calibration with gain of voltage and current.
+    pub fn new(voltage: f32, current: f32) -> Self {
+        Self { voltage, current }
+    }
+
+    /// Return gain of power and energy.
+    pub fn power(&self) -> f32 {
+        self.voltage * self.current
+    }
+
+    /// Return true if both gains are finite and greater than 0.
+    fn is_valid(&self) -> bool {
+        [self.voltage, self.current]
+            .iter()
+            .all(|gain| gain.is_finite() && *gain > 0.0)
+    }
+}
+
+/// Direction of energy on channel, from sign byte of power
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Energy flows from grid to load, counted in positive energy
+    Import,
+    /// Energy flows back to grid, counted in negative energy
+    Export,
+}
+
+/// Channel struct to get information. JSY MK 194 has 2 channels.
+/// Raw values are the integers returned by module, without calibration.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Channel {
     data_offset: usize,
     power_sign: usize,
-    voltage: f32,
-    current: f32,
-    positive_energy: f32,
-    negative_energy: f32,
-    power: f32,
-    factor: f32,
+    calibration: Calibration,
+    voltage: u32,
+    current: u32,
+    positive_energy: u32,
+    negative_energy: u32,
+    power: u32,
+    is_power_negative: bool,
+    factor: u32,
 }
 
 impl Channel {
@@ -167,55 +438,295 @@ impl Channel {
         Self {
             data_offset,
             power_sign,
-            voltage: 0.0,
-            current: 0.0,
-            positive_energy: 0.0,
-            negative_energy: 0.0,
-            power: 0.0,
-            factor: 0.0,
+            calibration: Calibration::default(),
+            voltage: 0,
+            current: 0,
+            positive_energy: 0,
+            negative_energy: 0,
+            power: 0,
+            is_power_negative: false,
+            factor: 0,
         }
     }
 
+    /// Set calibration applied to measures of channel.
+    pub fn with_calibration(mut self, calibration: Calibration) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    /// Return calibration applied to measures of channel.
+    pub fn calibration(&self) -> &Calibration {
+        &self.calibration
+    }
+
     /// Return the voltage of first channel in volt.
     pub fn voltage(&self) -> f32 {
-        self.voltage
+        (self.voltage as f32) * 0.0001 * self.calibration.voltage
     }
 
     /// Return current in A of channel.
     pub fn current(&self) -> f32 {
-        self.current
+        (self.current as f32) * 0.0001 * self.calibration.current
     }
 
     /// Return positive energy in kW/h of channel.
     pub fn positive_energy(&self) -> f32 {
-        self.positive_energy
+        (self.positive_energy as f32) * 0.0001 * self.calibration.power()
     }
 
     /// Return negative energy in kW/h of channel.
     pub fn negative_energy(&self) -> f32 {
-        self.negative_energy
+        (self.negative_energy as f32) * 0.0001 * self.calibration.power()
     }
 
     /// Return the power of channel in watt.
     pub fn power(&self) -> f32 {
-        self.power
+        let p = (self.power as f32) * 0.0001;
+
+        if self.is_power_negative && p > 0.0 {
+            return -p * self.calibration.power();
+        }
+
+        p * self.calibration.power()
     }
 
     /// Return the power of channel in watt.
     pub fn factor(&self) -> f32 {
+        (self.factor as f32) * 0.001
+    }
+
+    /// Return direction of energy, set by module even when power is 0 W.
+    pub fn direction(&self) -> Direction {
+        if self.is_power_negative {
+            Direction::Export
+        } else {
+            Direction::Import
+        }
+    }
+
+    /// Return current in A, negative on export.
+    pub fn signed_current(&self) -> f32 {
+        self.sign() * self.current()
+    }
+
+    /// Return power factor, negative on export.
+    pub fn signed_factor(&self) -> f32 {
+        self.sign() * self.factor()
+    }
+
+    /// Return the voltage in volt, computed in f64.
+    pub fn voltage_f64(&self) -> f64 {
+        (self.voltage as f64) * 0.0001 * self.calibration.voltage as f64
+    }
+
+    /// Return current in A, computed in f64.
+    pub fn current_f64(&self) -> f64 {
+        (self.current as f64) * 0.0001 * self.calibration.current as f64
+    }
+
+    /// Return positive energy in kW/h, computed in f64.
+    pub fn positive_energy_f64(&self) -> f64 {
+        (self.positive_energy as f64) * 0.0001 * self.calibration.power() as f64
+    }
+
+    /// Return negative energy in kW/h, computed in f64.
+    pub fn negative_energy_f64(&self) -> f64 {
+        (self.negative_energy as f64) * 0.0001 * self.calibration.power() as f64
+    }
+
+    /// Return the power in watt, computed in f64.
+    pub fn power_f64(&self) -> f64 {
+        (self.power_raw() as f64) * 0.0001 * self.calibration.power() as f64
+    }
+
+    /// Return power factor, computed in f64.
+    pub fn factor_f64(&self) -> f64 {
+        (self.factor as f64) * 0.001
+    }
+
+    /// Return apparent power (voltage x current) in VA.
+    pub fn apparent_power(&self) -> f32 {
+        self.apparent_power_f64() as f32
+    }
+
+    /// Return reactive power in var. Module doesn't give if load is inductive or
+    /// capacitive, so value is always positive.
+    pub fn reactive_power(&self) -> f32 {
+        self.reactive_power_f64() as f32
+    }
+
+    /// Return angle between voltage and current in degree, from power factor.
+    /// 0 to 90 on import, 90 to 180 on export.
+    pub fn phase_angle(&self) -> f32 {
+        self.phase_angle_f64() as f32
+    }
+
+    /// Return apparent power in VA, computed in f64.
+    pub fn apparent_power_f64(&self) -> f64 {
+        self.voltage_f64() * self.current_f64()
+    }
+
+    /// Return reactive power in var, computed in f64.
+    /// Active power greater than apparent power by rounding gives 0 var.
+    pub fn reactive_power_f64(&self) -> f64 {
+        let apparent_power = self.apparent_power_f64();
+        let power = self.power_f64();
+
+        libm::sqrt((apparent_power * apparent_power - power * power).max(0.0))
+    }
+
+    /// Return phase angle in degree, computed in f64.
+    /// Power factor greater than 1 by rounding is read as 1.
+    pub fn phase_angle_f64(&self) -> f64 {
+        let factor = self.factor_f64().min(1.0);
+        let angle = libm::acos(factor).to_degrees();
+
+        match self.direction() {
+            Direction::Import => angle,
+            Direction::Export => 180.0 - angle,
+        }
+    }
+
+    /// Return raw voltage in 0.1 mV.
+    pub fn voltage_raw(&self) -> u32 {
+        self.voltage
+    }
+
+    /// Return raw current in 0.1 mA.
+    pub fn current_raw(&self) -> u32 {
+        self.current
+    }
+
+    /// Return raw positive energy in 0.1 Wh.
+    pub fn positive_energy_raw(&self) -> u32 {
+        self.positive_energy
+    }
+
+    /// Return raw negative energy in 0.1 Wh.
+    pub fn negative_energy_raw(&self) -> u32 {
+        self.negative_energy
+    }
+
+    /// Return raw power with sign in 0.1 mW.
+    pub fn power_raw(&self) -> i64 {
+        if self.is_power_negative {
+            return -(self.power as i64);
+        }
+
+        self.power as i64
+    }
+
+    /// Return raw power factor in 0.001.
+    pub fn factor_raw(&self) -> u32 {
         self.factor
     }
 
+    /// Return raw current in 0.1 mA, negative on export.
+    pub fn signed_current_raw(&self) -> i64 {
+        match self.direction() {
+            Direction::Import => self.current as i64,
+            Direction::Export => -(self.current as i64),
+        }
+    }
+
+    /// Return raw power factor in 0.001, negative on export.
+    pub fn signed_factor_raw(&self) -> i64 {
+        match self.direction() {
+            Direction::Import => self.factor as i64,
+            Direction::Export => -(self.factor as i64),
+        }
+    }
+
+    /// Return 1 on import, -1 on export.
+    fn sign(&self) -> f32 {
+        match self.direction() {
+            Direction::Import => 1.0,
+            Direction::Export => -1.0,
+        }
+    }
+
     /// Update all data
-    fn update(&mut self, segment_read: &[u8; SEGMENT_READ]) {
-        self.voltage = (get_data(segment_read, self.data_offset + VOLTAGE) as f32) * 0.0001;
-        self.current = (get_data(segment_read, self.data_offset + CURRENT) as f32) * 0.0001;
-        self.positive_energy =
-            (get_data(segment_read, self.data_offset + POSITIVE_ENERGY) as f32) * 0.0001;
-        self.negative_energy =
-            (get_data(segment_read, self.data_offset + NEGATIVE_ENERGY) as f32) * 0.0001;
-        self.factor = (get_data(segment_read, self.data_offset + FACTOR) as f32) * 0.001;
-        self.power = power(segment_read, self.data_offset + POWER, self.power_sign);
+    fn update(&mut self, segment_read: &[u8]) {
+        self.voltage = get_data(segment_read, self.data_offset + VOLTAGE);
+        self.current = get_data(segment_read, self.data_offset + CURRENT);
+        self.positive_energy = get_data(segment_read, self.data_offset + POSITIVE_ENERGY);
+        self.negative_energy = get_data(segment_read, self.data_offset + NEGATIVE_ENERGY);
+        self.factor = get_data(segment_read, self.data_offset + FACTOR);
+        self.power = get_data(segment_read, self.data_offset + POWER);
+        self.is_power_negative = segment_read[self.power_sign] == 1;
+    }
+}
+
+/// Data of both channels and frequency read at once
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    sequence: u32,
+    channel1: Channel,
+    channel2: Channel,
+    frequency: u32,
+}
+
+impl Measurement {
+    /// Create empty measurement, with calibration of each channel.
+    fn new(calibration1: Calibration, calibration2: Calibration) -> Self {
+        Self {
+            sequence: 0,
+            channel1: Channel::new(CHANNEL_1_OFFSET, POWER_SIGN_1).with_calibration(calibration1),
+            channel2: Channel::new(CHANNEL_2_OFFSET, POWER_SIGN_2).with_calibration(calibration2),
+            frequency: 0,
+        }
+    }
+
+    /// Return number of read, from 1. Two measurements with same sequence are same read.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// Return first channel.
+    pub fn channel1(&self) -> &Channel {
+        &self.channel1
+    }
+
+    /// Return second channel.
+    pub fn channel2(&self) -> &Channel {
+        &self.channel2
+    }
+
+    /// Return frequency in hz.
+    pub fn frequency(&self) -> f32 {
+        (self.frequency as f32) * 0.01
+    }
+
+    /// Return frequency in hz, computed in f64.
+    pub fn frequency_f64(&self) -> f64 {
+        (self.frequency as f64) * 0.01
+    }
+
+    /// Return raw frequency in 0.01 hz.
+    pub fn frequency_raw(&self) -> u32 {
+        self.frequency
+    }
+
+    /// Return measurement decoded from response, with same calibration and sequence.
+    fn decode(&self, response: &[u8]) -> Self {
+        let mut measurement = *self;
+
+        measurement.channel1.update(response);
+        measurement.channel2.update(response);
+        measurement.frequency = get_data(response, FREQUENCY);
+
+        measurement
+    }
+
+    /// Return next measurement from response.
+    fn next(&self, response: &[u8]) -> Self {
+        let mut measurement = self.decode(response);
+
+        // Sequence 0 is kept for no read
+        measurement.sequence = self.sequence.wrapping_add(1).max(1);
+
+        measurement
     }
 }
 
@@ -225,15 +736,11 @@ where
     U: Uart,
     D: DelayNs,
 {
-    uart: U,
-    delay: D,
-    segment_write: [u8; SEGMENT_WRITE], //= {0x01, 0x03, 0x00, 0x48, 0x00, 0x0E, 0x44, 0x18};
-    segment_read: [u8; SEGMENT_READ],
-    is_crc_valid: CrcCheck,
-    frequency: f32,
-
-    pub channel1: Channel,
-    pub channel2: Channel,
+    link: Link<U, D>,
+    address: u8,
+    timeout_ms: u32,
+    retry_policy: RetryPolicy,
+    measurement: Measurement,
 }
 
 impl<U, D> JsyMk194<U, D>
@@ -241,135 +748,308 @@ where
     U: Uart,
     D: DelayNs,
 {
-    /// Create a new struct of JsyMk194.
+    /// Create a new struct of JsyMk194 with default configuration.
+    /// See [`JsyMk194Builder`] to change configuration, like slave address.
     pub fn new(uart: U, delay: D) -> Self {
-        Self {
-            uart,
-            delay,
-            segment_write: [0x01, 0x03, 0x00, 0x48, 0x00, 0x0e, 0x44, 0x18],
-            segment_read: [0; SEGMENT_READ],
-            is_crc_valid: is_crc_ok,
-            channel1: Channel::new(CHANNEL_1_OFFSET, POWER_SIGN_1),
-            channel2: Channel::new(CHANNEL_2_OFFSET, POWER_SIGN_2),
-            frequency: 0.0,
-        }
+        JsyMk194Builder::new(uart, delay).into_device()
     }
 
-    /// Create a new struct of JsyMk194.
+    /// Create a new struct of JsyMk194 without check of CRC.
     pub fn new_without_crc_check(uart: U, delay: D) -> Self {
-        Self {
-            uart,
-            delay,
-            segment_write: [0x01, 0x03, 0x00, 0x48, 0x00, 0x0e, 0x44, 0x18],
-            segment_read: [0; SEGMENT_READ],
-            is_crc_valid: crc_always_ok,
-            channel1: Channel::new(CHANNEL_1_OFFSET, POWER_SIGN_1),
-            channel2: Channel::new(CHANNEL_2_OFFSET, POWER_SIGN_2),
-            frequency: 0.0,
-        }
+        JsyMk194Builder::new(uart, delay)
+            .with_crc_check(false)
+            .into_device()
     }
 
-    // Read and wait 100ms
-    pub fn read(&mut self) -> Result<(), error::UartError> {
-        self.read_with_timeout(100)
-    }
-
-    /// Read data.
-    pub fn read_with_timeout(&mut self, timeout_ms: u32) -> Result<(), error::UartError> {
-        // send segment to JSY-MK-194
-        self.uart.write(&self.segment_write)?;
-
-        let is_read_data = self.uart.read(&mut self.segment_read, timeout_ms);
-
-        match is_read_data {
-            Ok(data_size) => {
-                if data_size != READ_DATA_SIZE {
-                    return Err(error::UartError::new(
-                        error::UartErrorKind::ReadInsuffisantBytes,
-                        format!(
-                            "Try to read {} bytes, but Uart read only {} bytes",
-                            READ_DATA_SIZE, data_size
-                        ),
-                    ));
-                }
-
-                if (self.is_crc_valid)(&self.segment_read[0..data_size]) {
-                    self.channel1.update(&self.segment_read);
-                    self.channel2.update(&self.segment_read);
-                    self.frequency = (get_data(&self.segment_read, FREQUENCY) as f32) * 0.01;
-                    Ok(())
-                } else {
-                    Err(error::UartError::from(error::UartErrorKind::BadCrc))
-                }
-            }
-            Err(e) => Err(e),
-        }
+    /// Return slave address of module.
+    pub fn address(&self) -> u8 {
+        self.address
     }
 
-    /// Return frequency in hz.
-    pub fn frequency(&self) -> f32 {
-        self.frequency
+    /// Set retry policy of reads and writes. Default is one attempt.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Return retry policy of reads and writes.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Return time to wait whole response in addition to its transmit time,
+    /// used by methods without timeout.
+    pub fn timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    // Read and wait default timeout
+    pub fn read(&mut self) -> Result<Measurement, JsyError<U::Error>> {
+        self.read_with_timeout(self.timeout_ms)
+    }
+
+    /// Read data. `timeout_ms` is the time to wait whole response in addition to
+    /// its transmit time at bitrate of UART.
+    /// Last measurement is kept if read fails.
+    pub fn read_with_timeout(
+        &mut self,
+        timeout_ms: u32,
+    ) -> Result<Measurement, JsyError<U::Error>> {
+        let address = self.address;
+
+        self.retry_policy.run(&mut self.link, |link| {
+            link.read_response(address, DATA_REGISTER, DATA_REGISTER_COUNT, timeout_ms)
+        })?;
+
+        self.measurement = self.measurement.next(self.link.segment_read());
+
+        Ok(self.measurement)
+    }
+
+    /// Return last measurement successfully read, `None` before first read.
+    pub fn last_measurement(&self) -> Option<Measurement> {
+        (self.measurement.sequence > 0).then_some(self.measurement)
+    }
+
+    /// Read `count` registers from `start` and wait default timeout.
+    pub fn read_registers(
+        &mut self,
+        start: u16,
+        count: u16,
+    ) -> Result<Registers, JsyError<U::Error>> {
+        self.read_registers_with_timeout(start, count, self.timeout_ms)
+    }
+
+    /// Read `count` registers from `start`.
+    /// Return raw values, see [`Registers`].
+    pub fn read_registers_with_timeout(
+        &mut self,
+        start: u16,
+        count: u16,
+        timeout_ms: u32,
+    ) -> Result<Registers, JsyError<U::Error>> {
+        let address = self.address;
+
+        self.retry_policy.run(&mut self.link, |link| {
+            link.read_registers(address, start, count, timeout_ms)
+        })
+    }
+
+    /// Read model, measurement range, address and bitrate of module.
+    pub fn device_info(&mut self) -> Result<DeviceInfo, JsyError<U::Error>> {
+        let registers = self.read_registers(SYSTEM_REGISTER, SYSTEM_REGISTER_COUNT)?;
+
+        Ok(DeviceInfo::from_registers(&registers))
+    }
+
+    /// Write `values` in registers from `start` and wait acknowledgement default timeout.
+    pub fn write_registers(
+        &mut self,
+        start: u16,
+        values: &[u16],
+    ) -> Result<(), JsyError<U::Error>> {
+        self.write_registers_with_timeout(start, values, self.timeout_ms)
+    }
+
+    /// Write `values` in registers from `start`.
+    /// Module acknowledge by echo address, function, start and count of request.
+    pub fn write_registers_with_timeout(
+        &mut self,
+        start: u16,
+        values: &[u16],
+        timeout_ms: u32,
+    ) -> Result<(), JsyError<U::Error>> {
+        let address = self.address;
+
+        self.retry_policy.run(&mut self.link, |link| {
+            link.write_registers(address, start, values, timeout_ms)
+        })
     }
 
     /// Default bitrate is 4800, you can update the bitrate of module
     /// the available values are : 4800, 9600, 19200, 38400.
-    /// Return true if success.
+    /// Address of module is kept.
+    /// UART is not updated, see [`Self::switch_bitrate`] to update both and wait
+    /// module applies new bitrate.
     pub fn change_bitrate(
         &mut self,
         new_bitrate: ChangeBitrate,
-    ) -> Result<(), error::ChangeBitrateError> {
-        let mut segment: [u8; SEGMENT_WRITE_CHANGE_BIT_RATE] = [
-            0x00, 0x10, 0x00, 0x04, 0x00, 0x01, 0x02, 0x01, 0x00, 0x00, 0x00,
-        ];
-
-        match new_bitrate {
-            ChangeBitrate::B9600 => self.update_segment(&mut segment, 0x06, 0x2b, 0xd6),
-            ChangeBitrate::B19200 => self.update_segment(&mut segment, 0x07, 0xea, 0x16),
-            ChangeBitrate::B38400 => self.update_segment(&mut segment, 0x08, 0xaa, 0x12),
-            _ => self.update_segment(&mut segment, 0x05, 0x6B, 0xD7),
-        }
+    ) -> Result<(), error::ChangeBitrateError<U::Error>> {
+        let registers = self
+            .read_registers(ADDRESS_BITRATE_REGISTER, 1)
+            .map_err(error::ChangeBitrateError::new)?;
+        let address = (registers[0] >> 8) as u8;
+
+        self.write_address_bitrate(address, new_bitrate.code())
+            .map_err(error::ChangeBitrateError::new)
+    }
+
+    /// Reset positive and negative energy of both channels.
+    pub fn reset_energy(&mut self) -> Result<(), JsyError<U::Error>> {
+        self.write_registers(ENERGY_RESET_REGISTER, &[0x0000, 0x0000])
+    }
+
+    /// Reset positive and negative energy of first channel.
+    /// Energy registers are read/write in register table of instruction book.
+    pub fn reset_energy1(&mut self) -> Result<(), JsyError<U::Error>> {
+        self.reset_channel_energy(0)
+    }
+
+    /// Reset positive and negative energy of second channel.
+    pub fn reset_energy2(&mut self) -> Result<(), JsyError<U::Error>> {
+        self.reset_channel_energy(CHANNEL_2_REGISTER_OFFSET)
+    }
+
+    /// Write zero in energy registers of channel, 32 bits registers are not contiguous.
+    fn reset_channel_energy(&mut self, register_offset: u16) -> Result<(), JsyError<U::Error>> {
+        self.write_registers(
+            POSITIVE_ENERGY_1_REGISTER + register_offset,
+            &[0x0000, 0x0000],
+        )?;
+        self.write_registers(
+            NEGATIVE_ENERGY_1_REGISTER + register_offset,
+            &[0x0000, 0x0000],
+        )
+    }
+
+    /// Change slave address of module (1 to 247). Bitrate of module is kept.
+    /// Driver use new address after success.
+    pub fn change_address(&mut self, new_address: u8) -> Result<(), JsyError<U::Error>> {
+        check_address(new_address)?;
+
+        let registers = self.read_registers(ADDRESS_BITRATE_REGISTER, 1)?;
+        let bitrate_code = (registers[0] & 0x00ff) as u8;
+
+        self.write_address_bitrate(new_address, bitrate_code)
+    }
+
+    /// Change slave address (1 to 247) and bitrate of module in one request.
+    /// Driver use new address after success, UART must be updated with [`Self::change_baudrate`].
+    pub fn change_address_and_bitrate(
+        &mut self,
+        new_address: u8,
+        new_bitrate: ChangeBitrate,
+    ) -> Result<(), JsyError<U::Error>> {
+        check_address(new_address)?;
+
+        self.write_address_bitrate(new_address, new_bitrate.code())
+    }
+
+    /// Write address and bitrate register. Module acknowledge with current address.
+    fn write_address_bitrate(
+        &mut self,
+        new_address: u8,
+        bitrate_code: u8,
+    ) -> Result<(), JsyError<U::Error>> {
+        self.write_registers(
+            ADDRESS_BITRATE_REGISTER,
+            &[(new_address as u16) << 8 | bitrate_code as u16],
+        )?;
 
-        self.delay.delay_ms(1000);
+        self.address = new_address;
+
+        Ok(())
+    }
+
+    /// Try each bitrate of [`ChangeBitrate::ALL`] on UART until module answers.
+    /// UART stays configured with detected bitrate, or is configured back with
+    /// previous bitrate if module doesn't answer or UART fails.
+    /// `probe_timeout_ms` is the time to wait response for each bitrate.
+    pub fn detect_baudrate(
+        &mut self,
+        probe_timeout_ms: u32,
+    ) -> Result<ChangeBitrate, JsyError<U::Error>> {
+        let previous_baudrate = self.link.baudrate();
 
-        let result = self.uart.write(&segment);
+        let error = match self.probe_bitrates(probe_timeout_ms) {
+            Ok(Some(bitrate)) => return Ok(bitrate),
+            Ok(None) => error::UartError::from(error::UartErrorKind::BitrateNotDetected)
+                .with_detail(error::ErrorDetail::Slave(self.address))
+                .into(),
+            Err(e) => e,
+        };
 
-        match result {
-            Ok(write_size) => {
-                if write_size == segment.len() {
-                    return Ok(());
-                }
+        self.change_baudrate(previous_baudrate)?;
 
-                Err(error::ChangeBitrateError::new(error::UartError::new(
-                    error::UartErrorKind::WriteInsuffisantBytes,
-                    format!(
-                        "Try to write {} bytes, but Uart write only {} bytes",
-                        segment.len(),
-                        write_size
-                    ),
-                )))
+        Err(error)
+    }
+
+    /// Probe module at each bitrate. Return `None` if module doesn't answer.
+    /// UART errors are returned, they don't depend on bitrate.
+    fn probe_bitrates(
+        &mut self,
+        probe_timeout_ms: u32,
+    ) -> Result<Option<ChangeBitrate>, JsyError<U::Error>> {
+        for bitrate in ChangeBitrate::ALL {
+            self.change_baudrate(bitrate.bitrate())?;
+
+            // Probe is not retried, next bitrate is tried instead
+            match self.link.read_registers(
+                self.address,
+                ADDRESS_BITRATE_REGISTER,
+                1,
+                probe_timeout_ms,
+            ) {
+                Ok(_) => return Ok(Some(bitrate)),
+                Err(e) if !e.is_transport() => (),
+                Err(e) => return Err(e),
             }
-            Err(e) => Err(error::ChangeBitrateError::new(e)),
         }
+
+        Ok(None)
+    }
+
+    /// Change bitrate of module, then bitrate of UART, and check module answers
+    /// at new bitrate after 1s to apply it. If not, UART is configured back with
+    /// previous bitrate.
+    pub fn switch_bitrate(
+        &mut self,
+        new_bitrate: ChangeBitrate,
+    ) -> Result<(), error::ChangeBitrateError<U::Error>> {
+        let previous_baudrate = self.link.baudrate();
+
+        match self.write_address_bitrate(self.address, new_bitrate.code()) {
+            Ok(()) => (),
+            // Module may already use new bitrate to acknowledge, link is checked below
+            Err(e) if is_link_error(e.kind()) => (),
+            Err(e) => return Err(error::ChangeBitrateError::new(e)),
+        }
+
+        self.change_baudrate(new_bitrate.bitrate())
+            .map_err(error::ChangeBitrateError::new)?;
+
+        self.link.delay.delay_ms(BITRATE_SETTLE_MS);
+
+        // Module didn't answer or answered with another bitrate
+        if matches!(
+            self.read_registers(ADDRESS_BITRATE_REGISTER, 1),
+            Ok(registers) if (registers[0] & 0x00ff) as u8 == new_bitrate.code()
+        ) {
+            return Ok(());
+        }
+
+        self.change_baudrate(previous_baudrate)
+            .map_err(error::ChangeBitrateError::new)?;
+
+        Err(error::ChangeBitrateError::new(
+            error::UartError::from(error::UartErrorKind::BitrateNotVerified)
+                .with_detail(error::ErrorDetail::Bitrate(new_bitrate.bitrate()))
+                .into(),
+        ))
     }
 
-    pub fn change_baudrate(&mut self, f: u32) -> Result<(), UartError> {
-        self.uart.change_baudrate(f)
+    /// Change bitrate of UART.
+    pub fn change_baudrate(&mut self, f: u32) -> Result<(), JsyError<U::Error>> {
+        self.link.change_baudrate(f)
     }
 
-    fn update_segment(
-        &self,
-        data: &mut [u8; SEGMENT_WRITE_CHANGE_BIT_RATE],
-        value: u8,
-        crc1: u8,
-        crc2: u8,
-    ) {
-        data[8] = value;
-        data[9] = crc1;
-        data[10] = crc2;
+    /// Return bitrate of UART.
+    pub fn baudrate(&self) -> u32 {
+        self.link.baudrate()
     }
 
     #[cfg(test)]
     fn get_uart(&self) -> &U {
-        &self.uart
+        &self.link.uart
     }
 }
@@ -0,0 +1,349 @@
+//! Link with modules on UART. Send requests and check responses.
+use crate::error::{self, ErrorDetail, JsyError, UartError};
+use crate::modbus::{
+    self, ExceptionCode, Request, CRC_SIZE, EXCEPTION_FLAG, EXCEPTION_RESPONSE_SIZE,
+    WRITE_RESPONSE_SIZE,
+};
+use crate::{
+    read_response_size, register_count, CrcCheck, Registers, Uart, DEFAULT_BAUDRATE,
+    READ_HEADER_SIZE, SEGMENT_READ,
+};
+use embedded_hal::delay::DelayNs;
+
+/// Number of bits of one character on line (start, 8 data, parity or stop, stop)
+const CHARACTER_BITS: u32 = 11;
+/// Silent interval between frames is 3.5 characters
+const SILENT_CHARACTERS_X10: u32 = 35;
+/// Above 19200 bit/s, Modbus use fixed silent interval
+const MIN_SILENT_INTERVAL_US: u32 = 1750;
+
+/// Return silent interval between two frames in us.
+pub(crate) fn silent_interval_us(baudrate: u32) -> u32 {
+    if baudrate > 19200 {
+        return MIN_SILENT_INTERVAL_US;
+    }
+
+    (SILENT_CHARACTERS_X10 * CHARACTER_BITS * 1_000_000 / 10) / baudrate.max(1)
+}
+
+/// Return time to transmit `size` bytes in us.
+pub(crate) fn transmit_time_us(baudrate: u32, size: usize) -> u32 {
+    (size as u32 * CHARACTER_BITS * 1_000_000) / baudrate.max(1)
+}
+
+/// Return size of frame from header (address, function, byte count).
+/// `None` if function is unknown.
+fn frame_size_from_header(header: &[u8]) -> Option<usize> {
+    if header[1] & EXCEPTION_FLAG != 0 {
+        return Some(EXCEPTION_RESPONSE_SIZE);
+    }
+
+    match header[1] {
+        modbus::READ_HOLDING_REGISTERS => {
+            Some((READ_HEADER_SIZE + header[2] as usize + CRC_SIZE).min(SEGMENT_READ))
+        }
+        modbus::WRITE_MULTIPLE_REGISTERS => Some(WRITE_RESPONSE_SIZE),
+        _ => None,
+    }
+}
+
+/// UART and delay shared by all modules on same line
+pub(crate) struct Link<U, D>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    pub(crate) uart: U,
+    pub(crate) delay: D,
+    baudrate: u32,
+    segment_read: [u8; SEGMENT_READ],
+    is_crc_valid: CrcCheck,
+}
+
+impl<U, D> Link<U, D>
+where
+    U: Uart,
+    D: DelayNs,
+{
+    pub(crate) fn new(uart: U, delay: D, is_crc_valid: CrcCheck) -> Self {
+        Self {
+            uart,
+            delay,
+            baudrate: DEFAULT_BAUDRATE,
+            segment_read: [0; SEGMENT_READ],
+            is_crc_valid,
+        }
+    }
+
+    /// Set bitrate already configured on UART.
+    pub(crate) fn with_baudrate(mut self, baudrate: u32) -> Self {
+        self.baudrate = baudrate;
+        self
+    }
+
+    /// Return last response.
+    pub(crate) fn segment_read(&self) -> &[u8; SEGMENT_READ] {
+        &self.segment_read
+    }
+
+    /// Read `count` registers from `start` of module at `address`.
+    pub(crate) fn read_registers(
+        &mut self,
+        address: u8,
+        start: u16,
+        count: u16,
+        timeout_ms: u32,
+    ) -> Result<Registers, JsyError<U::Error>> {
+        let data_size = self.read_response(address, start, count, timeout_ms)?;
+
+        Ok(Registers::from_bytes(
+            &self.segment_read[READ_HEADER_SIZE..data_size - CRC_SIZE],
+        ))
+    }
+
+    /// Send read request and check response. Return size of response.
+    pub(crate) fn read_response(
+        &mut self,
+        address: u8,
+        start: u16,
+        count: u16,
+        timeout_ms: u32,
+    ) -> Result<usize, JsyError<U::Error>> {
+        let response_size = read_response_size(start, count);
+
+        if count == 0 || response_size > SEGMENT_READ {
+            // Response larger than buffer
+            return Err(error::UartError::from(error::UartErrorKind::InvalidRequest)
+                .with_detail(ErrorDetail::Registers { start, count })
+                .into());
+        }
+
+        let request = Request::read_holding_registers(address, start, count);
+
+        // send segment to JSY-MK-194
+        self.send(&request)?;
+
+        let data_size = self.read_frame(response_size, timeout_ms)?;
+
+        self.check_response(&request, data_size, response_size)?;
+
+        Ok(data_size)
+    }
+
+    /// Write `values` in registers from `start` of module at `address`.
+    /// Module acknowledge by echo address, function, start and count of request.
+    pub(crate) fn write_registers(
+        &mut self,
+        address: u8,
+        start: u16,
+        values: &[u16],
+        timeout_ms: u32,
+    ) -> Result<(), JsyError<U::Error>> {
+        let request = register_count(start, values.len())
+            .and_then(|count| Request::write_registers(address, start, count, values))
+            .ok_or_else(|| {
+                // Must be between 1 and MAX_WRITE_REGISTERS, and fill whole registers
+                error::UartError::from(error::UartErrorKind::InvalidRequest).with_detail(
+                    ErrorDetail::Registers {
+                        start,
+                        count: values.len() as u16,
+                    },
+                )
+            })?;
+
+        self.send(&request)?;
+
+        let data_size = self.read_frame(WRITE_RESPONSE_SIZE, timeout_ms)?;
+
+        self.check_response(&request, data_size, WRITE_RESPONSE_SIZE)?;
+
+        // Acknowledge is function, start and count of request
+        if self.segment_read[1..WRITE_RESPONSE_SIZE - CRC_SIZE]
+            != request.as_bytes()[1..WRITE_RESPONSE_SIZE - CRC_SIZE]
+        {
+            return Err(error::UartError::from(error::UartErrorKind::WriteRefused)
+                .with_detail(ErrorDetail::Registers {
+                    start,
+                    count: values.len() as u16,
+                })
+                .into());
+        }
+
+        Ok(())
+    }
+
+    /// Read response until frame is complete. Many UART return bytes available
+    /// in FIFO, so frame can be received in many parts.
+    /// Size of frame is `response_size`, then size announced by header of response.
+    /// Each read waits time left of `timeout_ms` plus time to transmit response,
+    /// bytes received take their transmit time from it. Stop when UART return no
+    /// byte. Return size of frame.
+    fn read_frame(
+        &mut self,
+        response_size: usize,
+        timeout_ms: u32,
+    ) -> Result<usize, JsyError<U::Error>> {
+        let mut remaining_us = timeout_ms
+            .saturating_mul(1000)
+            .saturating_add(transmit_time_us(self.baudrate, response_size));
+        let mut frame_size = response_size;
+        let mut received = 0;
+
+        while received < frame_size {
+            let size = self
+                .uart
+                .read(
+                    &mut self.segment_read[received..frame_size],
+                    remaining_us.div_ceil(1000),
+                )
+                .map_err(JsyError::read)?;
+
+            if size == 0 {
+                break;
+            }
+
+            received += size;
+            remaining_us = remaining_us.saturating_sub(transmit_time_us(self.baudrate, size));
+
+            if received >= READ_HEADER_SIZE {
+                frame_size = frame_size_from_header(&self.segment_read).unwrap_or(response_size);
+            }
+        }
+
+        Ok(received.min(frame_size))
+    }
+
+    /// Check size, CRC and header (address, function, byte count) of response
+    /// against request. Exception response of module is returned as error.
+    fn check_response(
+        &self,
+        request: &Request,
+        data_size: usize,
+        response_size: usize,
+    ) -> Result<(), UartError> {
+        let response = &self.segment_read[0..data_size];
+        let is_exception =
+            data_size == EXCEPTION_RESPONSE_SIZE && response[1] & EXCEPTION_FLAG != 0;
+        // Frame with size announced by its header, header is checked below
+        let is_complete = data_size == response_size
+            || (data_size >= READ_HEADER_SIZE
+                && frame_size_from_header(response) == Some(data_size));
+
+        if !is_complete {
+            return Err(
+                error::UartError::from(error::UartErrorKind::ReadInsuffisantBytes).with_detail(
+                    ErrorDetail::Size {
+                        expected: response_size,
+                        actual: data_size,
+                    },
+                ),
+            );
+        }
+
+        if !(self.is_crc_valid)(response) {
+            return Err(error::UartError::from(error::UartErrorKind::BadCrc));
+        }
+
+        if response[0] != request.address() {
+            return Err(
+                error::UartError::from(error::UartErrorKind::WrongAddress).with_detail(
+                    ErrorDetail::Address {
+                        expected: request.address(),
+                        actual: response[0],
+                    },
+                ),
+            );
+        }
+
+        let function = response[1] & !EXCEPTION_FLAG;
+
+        if function != request.function() {
+            return Err(
+                error::UartError::from(error::UartErrorKind::WrongFunction).with_detail(
+                    ErrorDetail::Function {
+                        expected: request.function(),
+                        actual: function,
+                    },
+                ),
+            );
+        }
+
+        if is_exception {
+            let code = ExceptionCode::from(response[2]);
+
+            return Err(error::UartError::from(error::UartErrorKind::Exception(
+                code,
+            )));
+        }
+
+        if data_size != response_size {
+            return Err(
+                error::UartError::from(error::UartErrorKind::WrongByteCount).with_detail(
+                    ErrorDetail::Size {
+                        expected: response_size,
+                        actual: data_size,
+                    },
+                ),
+            );
+        }
+
+        // Byte count announced by header of read response
+        if function == modbus::READ_HOLDING_REGISTERS
+            && response[2] as usize != response_size - READ_HEADER_SIZE - CRC_SIZE
+        {
+            return Err(
+                error::UartError::from(error::UartErrorKind::WrongByteCount).with_detail(
+                    ErrorDetail::Size {
+                        expected: response_size - READ_HEADER_SIZE - CRC_SIZE,
+                        actual: response[2] as usize,
+                    },
+                ),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Send request to module, after silent interval and clear of late bytes
+    /// of previous response.
+    pub(crate) fn send(&mut self, request: &Request) -> Result<(), JsyError<U::Error>> {
+        self.wait_silence();
+        self.uart.clear_input().map_err(JsyError::read)?;
+
+        let write_size = self
+            .uart
+            .write(request.as_bytes())
+            .map_err(JsyError::write)?;
+
+        if write_size != request.len() {
+            return Err(
+                error::UartError::from(error::UartErrorKind::WriteInsuffisantBytes)
+                    .with_detail(ErrorDetail::Size {
+                        expected: request.len(),
+                        actual: write_size,
+                    })
+                    .into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Wait silent interval required between two frames.
+    fn wait_silence(&mut self) {
+        self.delay.delay_us(silent_interval_us(self.baudrate));
+    }
+
+    /// Change bitrate of UART.
+    pub(crate) fn change_baudrate(&mut self, f: u32) -> Result<(), JsyError<U::Error>> {
+        self.uart.change_baudrate(f).map_err(JsyError::other)?;
+        self.baudrate = f;
+
+        Ok(())
+    }
+
+    /// Return bitrate of UART.
+    pub(crate) fn baudrate(&self) -> u32 {
+        self.baudrate
+    }
+}
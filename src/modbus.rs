@@ -0,0 +1,229 @@
+//! Modbus RTU frame codec used to talk with JSY-MK-194.
+//!
+//! Every request is built with the CRC computed at runtime, so no frame has to
+//! be hand-calculated.
+//!
+//! | Function                         | Request                                                              |
+//! |----------------------------------|----------------------------------------------------------------------|
+//! | Read Holding Registers (0x03)    | address, 0x03, start (2 bytes), count (2 bytes), crc                 |
+//! | Write Multiple Registers (0x10)  | address, 0x10, start (2 bytes), count (2 bytes), byte count, values, crc |
+//!
+//! When module cannot execute request, it answers with an exception:
+//! address, function | 0x80, exception code, crc.
+//!
+use core::fmt;
+
+/// Broadcast address, every module on bus execute request but never answer
+pub const BROADCAST_ADDRESS: u8 = 0x00;
+/// First address can be assigned to a module
+pub const MIN_ADDRESS: u8 = 1;
+/// Last address can be assigned to a module
+pub const MAX_ADDRESS: u8 = 247;
+
+/// Function code to read holding registers
+pub const READ_HOLDING_REGISTERS: u8 = 0x03;
+/// Function code to write multiple registers
+pub const WRITE_MULTIPLE_REGISTERS: u8 = 0x10;
+
+/// Maximum size of a request
+pub const MAX_REQUEST_SIZE: usize = 64;
+/// Size of header of write multiple registers request (address, function, start, count, byte count)
+const WRITE_HEADER_SIZE: usize = 7;
+/// Size of CRC
+pub const CRC_SIZE: usize = 2;
+/// Size of write multiple registers response (address, function, start, count, crc)
+pub const WRITE_RESPONSE_SIZE: usize = 8;
+/// Size of exception response (address, function, exception code, crc)
+pub const EXCEPTION_RESPONSE_SIZE: usize = 5;
+/// Bit set in function code of exception response
+pub const EXCEPTION_FLAG: u8 = 0x80;
+/// Maximum number of registers can be written in one request
+pub const MAX_WRITE_REGISTERS: usize = (MAX_REQUEST_SIZE - WRITE_HEADER_SIZE - CRC_SIZE) / 2;
+
+/// Compute Modbus CRC of buffer.
+///
+/// From <https://ctlsys.com/support/how_to_compute_the_modbus_rtu_message_crc/>
+pub fn crc16(buf: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+
+    for current_byte in buf.iter() {
+        crc ^= *current_byte as u16; // XOR byte into least sig. byte of crc
+
+        for _ in (0..8).rev() {
+            // Loop over each bit
+            if (crc & 0x0001) != 0 {
+                // If the LSB is set
+                crc >>= 1; // Shift right and XOR 0xA001
+                crc ^= 0xA001;
+            } else {
+                // Else LSB is not set
+                crc >>= 1; // Just shift right
+            }
+        }
+    }
+    // Note, this number has low and high bytes swapped, so use it accordingly (or swap bytes)
+    crc
+}
+
+/// Check CRC stored in two last bytes of frame.
+pub fn is_crc_ok(buf: &[u8]) -> bool {
+    if buf.len() < CRC_SIZE {
+        return false;
+    }
+
+    let low = buf.len() - 2;
+    let hi = buf.len() - 1;
+    let buf_crc: u16 = (buf[hi] as u16) * 256 + (buf[low] as u16);
+
+    crc16(&buf[0..low]) == buf_crc
+}
+
+/// Exception code returned by module
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExceptionCode {
+    /// Function code is not supported by module
+    IllegalFunction,
+    /// Register is out of readable or writable range of module
+    IllegalDataAddress,
+    /// Value is out of range of register
+    IllegalDataValue,
+    /// Module failed to execute request
+    SlaveDeviceFailure,
+    /// Code not documented
+    Other(u8),
+}
+
+impl From<u8> for ExceptionCode {
+    fn from(code: u8) -> Self {
+        // Instruction book of JSY-MK-194 documents error codes as 0x81, 0x82 and 0x83
+        match code {
+            0x01 | 0x81 => ExceptionCode::IllegalFunction,
+            0x02 | 0x82 => ExceptionCode::IllegalDataAddress,
+            0x03 | 0x83 => ExceptionCode::IllegalDataValue,
+            0x04 | 0x84 => ExceptionCode::SlaveDeviceFailure,
+            _ => ExceptionCode::Other(code),
+        }
+    }
+}
+
+impl fmt::Display for ExceptionCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExceptionCode::IllegalFunction => write!(f, "illegal function"),
+            ExceptionCode::IllegalDataAddress => write!(f, "illegal data address"),
+            ExceptionCode::IllegalDataValue => write!(f, "illegal data value"),
+            ExceptionCode::SlaveDeviceFailure => write!(f, "slave device failure"),
+            ExceptionCode::Other(code) => write!(f, "unknown exception {:#04x}", code),
+        }
+    }
+}
+
+/// A Modbus request ready to be sent on UART.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    data: [u8; MAX_REQUEST_SIZE],
+    len: usize,
+}
+
+impl Request {
+    /// Build a Read Holding Registers (0x03) request.
+    pub fn read_holding_registers(address: u8, start: u16, count: u16) -> Self {
+        let mut request = Self::new(address, READ_HOLDING_REGISTERS);
+
+        request.push_u16(start);
+        request.push_u16(count);
+        request.push_crc();
+
+        request
+    }
+
+    /// Build a Write Multiple Registers (0x10) request.
+    /// Return `None` if `values` is empty or has more than [`MAX_WRITE_REGISTERS`] values.
+    pub fn write_multiple_registers(address: u8, start: u16, values: &[u16]) -> Option<Self> {
+        Self::write_registers(address, start, values.len() as u16, values)
+    }
+
+    /// Build a Write Multiple Registers (0x10) request of `count` registers.
+    /// A register can be 32 bits wide, so `count` can be lower than number of `values`.
+    /// Return `None` if `values` is empty or has more than [`MAX_WRITE_REGISTERS`] values,
+    /// or if `count` is 0 or greater than number of `values`.
+    pub fn write_registers(address: u8, start: u16, count: u16, values: &[u16]) -> Option<Self> {
+        if values.is_empty()
+            || values.len() > MAX_WRITE_REGISTERS
+            || count == 0
+            || count as usize > values.len()
+        {
+            return None;
+        }
+
+        let mut request = Self::new(address, WRITE_MULTIPLE_REGISTERS);
+
+        request.push_u16(start);
+        request.push_u16(count);
+        request.push((values.len() * 2) as u8);
+
+        for value in values {
+            request.push_u16(*value);
+        }
+
+        request.push_crc();
+
+        Some(request)
+    }
+
+    /// Return slave address of request.
+    pub fn address(&self) -> u8 {
+        self.data[0]
+    }
+
+    /// Return function code of request.
+    pub fn function(&self) -> u8 {
+        self.data[1]
+    }
+
+    /// Return bytes to send.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[0..self.len]
+    }
+
+    /// Return size of request.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return true if request is empty. Never happen for built request.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn new(address: u8, function: u8) -> Self {
+        let mut request = Self {
+            data: [0; MAX_REQUEST_SIZE],
+            len: 0,
+        };
+
+        request.push(address);
+        request.push(function);
+
+        request
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.data[self.len] = byte;
+        self.len += 1;
+    }
+
+    /// Modbus use big endian for register
+    fn push_u16(&mut self, value: u16) {
+        self.push((value >> 8) as u8);
+        self.push((value & 0xff) as u8);
+    }
+
+    /// CRC is sent low byte first
+    fn push_crc(&mut self) {
+        let crc = crc16(self.as_bytes());
+
+        self.push((crc & 0xff) as u8);
+        self.push((crc >> 8) as u8);
+    }
+}
@@ -0,0 +1,94 @@
+//! Retry policy of reads and writes.
+use crate::error::{JsyError, UartErrorKind};
+use crate::link::Link;
+use crate::modbus::ExceptionCode;
+use crate::Uart;
+use embedded_hal::delay::DelayNs;
+
+/// Return true if error can be solved by a new attempt
+pub type RetryCheck = fn(&UartErrorKind) -> bool;
+
+/// Default check: error on line (timeout, noise, crosstalk) and busy module are retryable.
+/// Invalid request and exception of module are not.
+pub fn is_transient_error(kind: &UartErrorKind) -> bool {
+    matches!(
+        kind,
+        UartErrorKind::Read
+            | UartErrorKind::ReadInsuffisantBytes
+            | UartErrorKind::BadCrc
+            | UartErrorKind::WrongAddress
+            | UartErrorKind::WrongFunction
+            | UartErrorKind::WrongByteCount
+            | UartErrorKind::Exception(ExceptionCode::SlaveDeviceFailure)
+    )
+}
+
+/// How many times a request is sent before error is returned
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    max_attempts: u8,
+    delay_ms: u32,
+    is_retryable: RetryCheck,
+}
+
+impl Default for RetryPolicy {
+    /// One attempt, no retry.
+    fn default() -> Self {
+        Self::new(1, 0)
+    }
+}
+
+impl RetryPolicy {
+    /// Create policy with `max_attempts` (at least 1) and `delay_ms` between two attempts.
+    pub fn new(max_attempts: u8, delay_ms: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            delay_ms,
+            is_retryable: is_transient_error,
+        }
+    }
+
+    /// Set which errors are retryable. Default is [`is_transient_error`].
+    pub fn with_retryable(mut self, is_retryable: RetryCheck) -> Self {
+        self.is_retryable = is_retryable;
+        self
+    }
+
+    /// Return maximum number of attempts.
+    pub fn max_attempts(&self) -> u8 {
+        self.max_attempts
+    }
+
+    /// Return delay between two attempts in ms.
+    pub fn delay_ms(&self) -> u32 {
+        self.delay_ms
+    }
+
+    /// Run `request` until success, not retryable error or last attempt.
+    /// Returned error has number of attempts made.
+    pub(crate) fn run<U, D, T>(
+        &self,
+        link: &mut Link<U, D>,
+        mut request: impl FnMut(&mut Link<U, D>) -> Result<T, JsyError<U::Error>>,
+    ) -> Result<T, JsyError<U::Error>>
+    where
+        U: Uart,
+        D: DelayNs,
+    {
+        let mut attempts = 1;
+
+        loop {
+            match request(link) {
+                Ok(value) => return Ok(value),
+                Err(e) if attempts < self.max_attempts && (self.is_retryable)(e.kind()) => {
+                    link.delay.delay_ms(self.delay_ms);
+                    attempts += 1;
+                }
+                Err(mut e) => {
+                    e.error_mut().attempts = attempts;
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! [`Uart`] for serial port implementing `embedded-io` traits.
+//!
+//! Available with `embedded-io` feature. Most HAL serial ports implement
+//! `Read`, `Write` and `ReadReady`, so no [`Uart`] has to be written:
+//! timeout of read is handled by polling `ReadReady` with `DelayNs`.
+use crate::Uart;
+use core::fmt;
+use embedded_hal::delay::DelayNs;
+use embedded_io::{ErrorType, Read, ReadReady, Write};
+
+/// Time between two checks of received bytes
+const POLL_INTERVAL_US: u32 = 100;
+
+/// Function to change bitrate of serial port, `embedded-io` doesn't configure port
+pub type ChangeBaudrate<S> = fn(&mut S, u32) -> Result<(), <S as ErrorType>::Error>;
+
+/// Error of [`SerialUart`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SerialError<E> {
+    /// Error of serial port
+    Io(E),
+    /// Bitrate can't be changed, see [`SerialUart::with_change_baudrate`]
+    ChangeBaudrateUnsupported,
+}
+
+impl<E> fmt::Display for SerialError<E>
+where
+    E: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerialError::Io(e) => write!(f, "serial port failed: {:?}", e),
+            SerialError::ChangeBaudrateUnsupported => {
+                write!(f, "bitrate of serial port can't be changed")
+            }
+        }
+    }
+}
+
+impl<E> core::error::Error for SerialError<E> where E: fmt::Debug {}
+
+/// Serial port and delay used as [`Uart`]
+pub struct SerialUart<S, D>
+where
+    S: Read + Write + ReadReady,
+    D: DelayNs,
+{
+    serial: S,
+    delay: D,
+    change_baudrate: Option<ChangeBaudrate<S>>,
+}
+
+impl<S, D> SerialUart<S, D>
+where
+    S: Read + Write + ReadReady,
+    D: DelayNs,
+{
+    /// Create UART from serial port. `delay` is used to wait bytes.
+    pub fn new(serial: S, delay: D) -> Self {
+        Self {
+            serial,
+            delay,
+            change_baudrate: None,
+        }
+    }
+
+    /// Set function to change bitrate of serial port. Without it, change of
+    /// bitrate returns [`SerialError::ChangeBaudrateUnsupported`].
+    pub fn with_change_baudrate(mut self, change_baudrate: ChangeBaudrate<S>) -> Self {
+        self.change_baudrate = Some(change_baudrate);
+        self
+    }
+
+    /// Return serial port.
+    pub fn serial(&self) -> &S {
+        &self.serial
+    }
+
+    /// Return mutable serial port.
+    pub fn serial_mut(&mut self) -> &mut S {
+        &mut self.serial
+    }
+
+    /// Return serial port and delay.
+    pub fn release(self) -> (S, D) {
+        (self.serial, self.delay)
+    }
+}
+
+impl<S, D> Uart for SerialUart<S, D>
+where
+    S: Read + Write + ReadReady,
+    D: DelayNs,
+{
+    type Error = SerialError<S::Error>;
+
+    fn read(&mut self, buf: &mut [u8], timeout: u32) -> Result<usize, Self::Error> {
+        let timeout_us = timeout.saturating_mul(1000);
+        let mut elapsed_us: u32 = 0;
+
+        // Read only when bytes are available, read of embedded-io blocks
+        while !self.serial.read_ready().map_err(SerialError::Io)? {
+            if elapsed_us >= timeout_us {
+                return Ok(0);
+            }
+
+            self.delay.delay_us(POLL_INTERVAL_US);
+            elapsed_us = elapsed_us.saturating_add(POLL_INTERVAL_US);
+        }
+
+        self.serial.read(buf).map_err(SerialError::Io)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, Self::Error> {
+        let mut written = 0;
+
+        while written < bytes.len() {
+            match self
+                .serial
+                .write(&bytes[written..])
+                .map_err(SerialError::Io)?
+            {
+                0 => break,
+                size => written += size,
+            }
+        }
+
+        // Request must be sent before response is read
+        self.serial.flush().map_err(SerialError::Io)?;
+
+        Ok(written)
+    }
+
+    fn change_baudrate(&mut self, f: u32) -> Result<(), Self::Error> {
+        let change_baudrate = self
+            .change_baudrate
+            .ok_or(SerialError::ChangeBaudrateUnsupported)?;
+
+        change_baudrate(&mut self.serial, f).map_err(SerialError::Io)
+    }
+}
@@ -1,7 +1,12 @@
 use embedded_hal::delay::DelayNs;
 
+/// Size of message to read
+const READ_DATA_SIZE: usize = 61;
+/// Size of message to write change bitrate
+const SEGMENT_WRITE_CHANGE_BIT_RATE: usize = 11;
+
 /// When put this data in segment_read, Uart.read() return Ok
-const READ_DATA_OK: [u8; crate::READ_DATA_SIZE] = [
+const READ_DATA_OK: [u8; READ_DATA_SIZE] = [
     0x01, 0x03, 0x38, 0x00, 0x24, 0x8E, 0x5F, 0x00, 0x00, 0x94, 0x0B, 0x00, 0x8A, 0xC4, 0xAA, 0x00,
     0x00, 0x2D, 0xB4, 0x00, 0x00, 0x03, 0xE8, 0x00, 0x00, 0x26, 0x16, 0x01, 0x01, 0x00, 0x00, 0x00,
     0x00, 0x13, 0x8B, 0x00, 0x24, 0x8E, 0x5F, 0x00, 0x00, 0x94, 0x0A, 0x00, 0x8A, 0x92, 0xD7, 0x00,
@@ -9,7 +14,7 @@ const READ_DATA_OK: [u8; crate::READ_DATA_SIZE] = [
 ];
 
 /// When put this data in segment_read, Uart.read() return Ok
-const READ_DATA_OK_2: [u8; crate::READ_DATA_SIZE] = [
+const READ_DATA_OK_2: [u8; READ_DATA_SIZE] = [
     0x01, 0x03, 0x38, 0x00, 0x23, 0xCF, 0x24, 0x00, 0x00, 0x03, 0xFD, 0x00, 0x02, 0x1F, 0x95, 0x00,
     0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x45, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
     0x00, 0x13, 0x89, 0x00, 0x23, 0xCF, 0x24, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
@@ -17,7 +22,7 @@ const READ_DATA_OK_2: [u8; crate::READ_DATA_SIZE] = [
 ];
 
 /// When put this data in segment_read, JsyMk194Hardware.read() return error, bad CRC
-const READ_DATA_BAD_CRC: [u8; crate::READ_DATA_SIZE] = [
+const READ_DATA_BAD_CRC: [u8; READ_DATA_SIZE] = [
     0x01, 0x03, 0x38, 0x00, 0x24, 0x8E, 0x5F, 0x00, 0x00, 0x94, 0x0B, 0x00, 0x8A, 0xC4, 0xAA, 0x00,
     0x00, 0x2D, 0xB4, 0x00, 0x00, 0x03, 0xE8, 0x00, 0x00, 0x26, 0x16, 0x01, 0x01, 0x00, 0x00, 0x00,
     0x00, 0x13, 0x8B, 0x00, 0x24, 0x8E, 0x5F, 0x00, 0x00, 0x94, 0x0A, 0x00, 0x8A, 0x92, 0xD7, 0x00,
@@ -25,61 +30,98 @@ const READ_DATA_BAD_CRC: [u8; crate::READ_DATA_SIZE] = [
 ];
 
 /// When put this data in segment_read, Uart.read() return Err
-const READ_DATA_ERROR: [u8; crate::READ_DATA_SIZE] = [0xff; crate::READ_DATA_SIZE];
+const READ_DATA_ERROR: [u8; READ_DATA_SIZE] = [0xff; READ_DATA_SIZE];
 
 /// When put this data in segment_read, Uart.read() return wrong siaz
-const READ_DATA_WRONG_SIZE: [u8; crate::READ_DATA_SIZE] = [0x01; crate::READ_DATA_SIZE];
+const READ_DATA_WRONG_SIZE: [u8; READ_DATA_SIZE] = [0x01; READ_DATA_SIZE];
 
 /// When put this data in segment_write, Uart.write() return Ok
-const WRITE_DATA_OK: [u8; crate::SEGMENT_WRITE_CHANGE_BIT_RATE] =
-    [0; crate::SEGMENT_WRITE_CHANGE_BIT_RATE];
+const WRITE_DATA_OK: [u8; SEGMENT_WRITE_CHANGE_BIT_RATE] = [0; SEGMENT_WRITE_CHANGE_BIT_RATE];
 
 /// When put this data in segment_write, Uart.write() return Err
-const WRITE_DATA_ERROR: [u8; crate::SEGMENT_WRITE_CHANGE_BIT_RATE] =
-    [0xff; crate::SEGMENT_WRITE_CHANGE_BIT_RATE];
+const WRITE_DATA_ERROR: [u8; SEGMENT_WRITE_CHANGE_BIT_RATE] = [0xff; SEGMENT_WRITE_CHANGE_BIT_RATE];
 
 struct UartTestImpl {
-    pub segment_write: [u8; crate::SEGMENT_WRITE_CHANGE_BIT_RATE],
-    pub segment_write_len: usize,
-    pub segment_read: [u8; crate::READ_DATA_SIZE],
+    /// Last written segment
+    pub segment_write: Vec<u8>,
+    /// All written segments
+    pub writes: Vec<Vec<u8>>,
+    pub write_error: bool,
+    /// Bytes returned by each read, when all are read, read returns no byte
+    pub responses: Vec<Vec<u8>>,
+    /// Bytes of current response not yet read
+    pub pending: Vec<u8>,
+    pub response_index: usize,
+    pub read_count: usize,
+    /// Timeout of each read
+    pub timeouts: Vec<u32>,
+    pub baudrate: u32,
+}
+
+/// Error of UartTestImpl
+#[derive(Debug, Clone, PartialEq)]
+enum UartTestError {
+    Read,
+    Write,
+}
+
+impl std::fmt::Display for UartTestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "test error {:?}", self)
+    }
 }
 
+impl std::error::Error for UartTestError {}
+
 impl crate::Uart for UartTestImpl {
-    fn read(&mut self, buf: &mut [u8], _timeout: u32) -> Result<usize, crate::error::UartError> {
-        match self.segment_read {
-            READ_DATA_WRONG_SIZE => Ok(1),
-            READ_DATA_ERROR => Err(crate::error::UartError::new(
-                crate::error::UartErrorKind::ReadInsuffisantBytes,
-                "Error read".to_string(),
-            )),
-            _ => {
-                for (index, data) in self.segment_read.into_iter().enumerate() {
-                    buf[index] = data;
-                }
+    type Error = UartTestError;
 
-                Ok(self.segment_read.len())
+    fn read(&mut self, buf: &mut [u8], timeout: u32) -> Result<usize, UartTestError> {
+        self.read_count += 1;
+        self.timeouts.push(timeout);
+
+        if self.pending.is_empty() {
+            let Some(response) = self.responses.get(self.response_index) else {
+                return Ok(0);
+            };
+
+            self.response_index += 1;
+
+            if response[..] == READ_DATA_WRONG_SIZE {
+                self.pending = vec![0x01];
+            } else if response[..] == READ_DATA_ERROR {
+                return Err(UartTestError::Read);
+            } else {
+                self.pending = response.clone();
             }
         }
-    }
 
-    fn write(&mut self, bytes: &[u8]) -> Result<usize, crate::error::UartError> {
-        self.segment_write_len = bytes.len();
+        let size = buf.len().min(self.pending.len());
 
-        if self.segment_write == WRITE_DATA_ERROR {
-            return Err(crate::error::UartError::new(
-                crate::error::UartErrorKind::WriteInsuffisantBytes,
-                "Error write".to_string(),
-            ));
-        }
+        buf[0..size].copy_from_slice(&self.pending[0..size]);
+        self.pending.drain(0..size);
+
+        Ok(size)
+    }
 
-        for (index, data) in bytes.iter().enumerate() {
-            self.segment_write[index] = *data;
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, UartTestError> {
+        if self.write_error {
+            return Err(UartTestError::Write);
         }
 
+        self.segment_write = bytes.to_vec();
+        self.writes.push(bytes.to_vec());
+
         Ok(bytes.len())
     }
-    
-    fn change_baudrate(&mut self, f: u32) -> Result<(), crate::error::UartError>  {
+
+    fn change_baudrate(&mut self, f: u32) -> Result<(), UartTestError> {
+        self.baudrate = f;
+        Ok(())
+    }
+
+    fn clear_input(&mut self) -> Result<(), UartTestError> {
+        self.pending.clear();
         Ok(())
     }
 }
@@ -90,47 +132,77 @@ impl DelayNs for DelayTestImpl {
     fn delay_ms(&mut self, _ms: u32) {
         // Do nothing
     }
-    
-    fn delay_ns(&mut self, ns: u32) {
-        todo!()
+
+    fn delay_ns(&mut self, _ns: u32) {
+        // Do nothing
     }
 }
 
-fn setup(
-    read_data: [u8; crate::READ_DATA_SIZE],
-    write_data: [u8; crate::SEGMENT_WRITE_CHANGE_BIT_RATE],
+/// Append CRC to frame
+fn with_crc(frame: &[u8]) -> Vec<u8> {
+    let crc = crate::modbus::crc16(frame);
+    let mut response = frame.to_vec();
+
+    response.push((crc & 0xff) as u8);
+    response.push((crc >> 8) as u8);
+
+    response
+}
+
+/// UART returning `responses`, one by one
+fn test_uart(responses: &[&[u8]]) -> UartTestImpl {
+    UartTestImpl {
+        segment_write: Vec::new(),
+        writes: Vec::new(),
+        write_error: false,
+        responses: responses.iter().map(|r| r.to_vec()).collect(),
+        pending: Vec::new(),
+        response_index: 0,
+        read_count: 0,
+        timeouts: Vec::new(),
+        baudrate: 4800,
+    }
+}
+
+fn setup_with_responses(
+    responses: &[&[u8]],
+    write_data: [u8; SEGMENT_WRITE_CHANGE_BIT_RATE],
 ) -> crate::JsyMk194<UartTestImpl, DelayTestImpl> {
-    let uart = UartTestImpl {
-        segment_write: write_data,
-        segment_read: read_data,
-        segment_write_len: 0,
-    };
+    let mut uart = test_uart(responses);
+    uart.write_error = write_data == WRITE_DATA_ERROR;
 
     let delay = DelayTestImpl {};
     crate::JsyMk194::new(uart, delay)
 }
 
+fn setup(
+    read_data: [u8; READ_DATA_SIZE],
+    write_data: [u8; SEGMENT_WRITE_CHANGE_BIT_RATE],
+) -> crate::JsyMk194<UartTestImpl, DelayTestImpl> {
+    setup_with_responses(&[&read_data], write_data)
+}
+
 #[test]
 fn test_read_ok() {
     let mut device = setup(READ_DATA_OK, WRITE_DATA_OK);
 
-    assert!(device.read().is_ok());
+    let measurement = device.read().unwrap();
 
-    let voltage_1 = device.channel1.voltage();
-    let current_1 = device.channel1.current();
-    let power_1 = device.channel1.power();
-    let positive_energy_1 = device.channel1.positive_energy();
-    let negative_energy_1 = device.channel1.negative_energy();
-    let factor_1 = device.channel1.factor();
+    let voltage_1 = measurement.channel1().voltage();
+    let current_1 = measurement.channel1().current();
+    let power_1 = measurement.channel1().power();
+    let positive_energy_1 = measurement.channel1().positive_energy();
+    let negative_energy_1 = measurement.channel1().negative_energy();
+    let factor_1 = measurement.channel1().factor();
 
-    let voltage_2 = device.channel2.voltage();
-    let current_2 = device.channel2.current();
-    let power_2 = device.channel2.power();
-    let positive_energy_2 = device.channel2.positive_energy();
-    let negative_energy_2 = device.channel2.negative_energy();
-    let factor_2 = device.channel2.factor();
+    let voltage_2 = measurement.channel2().voltage();
+    let current_2 = measurement.channel2().current();
+    let power_2 = measurement.channel2().power();
+    let positive_energy_2 = measurement.channel2().positive_energy();
+    let negative_energy_2 = measurement.channel2().negative_energy();
+    let factor_2 = measurement.channel2().factor();
 
-    let frequency = device.frequency();
+    let frequency = measurement.frequency();
 
     assert_eq!(voltage_1, 239.574_3);
     assert_eq!(current_1, 3.789_899_8);
@@ -153,23 +225,23 @@ fn test_read_ok() {
 fn test_read_ok_2() {
     let mut device = setup(READ_DATA_OK_2, WRITE_DATA_OK);
 
-    assert!(device.read().is_ok());
+    let measurement = device.read().unwrap();
 
-    let voltage_1 = device.channel1.voltage();
-    let current_1 = device.channel1.current();
-    let power_1 = device.channel1.power();
-    let positive_energy_1 = device.channel1.positive_energy();
-    let negative_energy_1 = device.channel1.negative_energy();
-    let factor_1 = device.channel1.factor();
+    let voltage_1 = measurement.channel1().voltage();
+    let current_1 = measurement.channel1().current();
+    let power_1 = measurement.channel1().power();
+    let positive_energy_1 = measurement.channel1().positive_energy();
+    let negative_energy_1 = measurement.channel1().negative_energy();
+    let factor_1 = measurement.channel1().factor();
 
-    let voltage_2 = device.channel2.voltage();
-    let current_2 = device.channel2.current();
-    let power_2 = device.channel2.power();
-    let positive_energy_2 = device.channel2.positive_energy();
-    let negative_energy_2 = device.channel2.negative_energy();
-    let factor_2 = device.channel2.factor();
+    let voltage_2 = measurement.channel2().voltage();
+    let current_2 = measurement.channel2().current();
+    let power_2 = measurement.channel2().power();
+    let positive_energy_2 = measurement.channel2().positive_energy();
+    let negative_energy_2 = measurement.channel2().negative_energy();
+    let factor_2 = measurement.channel2().factor();
 
-    let frequency = device.frequency();
+    let frequency = measurement.frequency();
 
     assert_eq!(voltage_1, 234.678_79);
     assert_eq!(current_1, 0.1021);
@@ -193,8 +265,8 @@ fn test_jsk_mk_196_read_method_return_error_cause_write_to_device_return_error()
     let mut device = setup(READ_DATA_OK, WRITE_DATA_ERROR);
 
     match device.read() {
-        Ok(()) => panic!(),
-        Err(e) => assert_eq!(e.kind, crate::error::UartErrorKind::WriteInsuffisantBytes),
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::Write),
     };
 }
 
@@ -203,8 +275,17 @@ fn test_jsk_mk_196_read_method_return_error_cause_read_to_device_return_error()
     let mut device = setup(READ_DATA_ERROR, WRITE_DATA_OK);
 
     match device.read() {
-        Ok(()) => panic!(),
-        Err(e) => assert_eq!(e.kind, crate::error::UartErrorKind::ReadInsuffisantBytes),
+        Ok(_) => panic!(),
+        Err(e) => {
+            assert_eq!(*e.kind(), crate::error::UartErrorKind::Read);
+            assert!(matches!(
+                e,
+                crate::error::JsyError::Transport {
+                    source: UartTestError::Read,
+                    ..
+                }
+            ));
+        }
     };
 }
 
@@ -213,24 +294,54 @@ fn test_jsk_mk_196_read_method_return_error_cause_read_to_device_return_wrong_si
     let mut device = setup(READ_DATA_WRONG_SIZE, WRITE_DATA_OK);
 
     match device.read() {
-        Ok(()) => panic!(),
-        Err(e) => assert_eq!(e.kind, crate::error::UartErrorKind::ReadInsuffisantBytes),
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::ReadInsuffisantBytes),
     };
 }
 
 #[test]
 fn test_jsk_mk_196_change_bitrate_method_return_true() {
-    let mut device = setup(READ_DATA_OK, WRITE_DATA_OK);
+    // Address 1, bitrate 4800
+    let register = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x05]);
+    let acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x04, 0x00, 0x01]);
+    let mut device = setup_with_responses(&[&register, &acknowledge], WRITE_DATA_OK);
 
     assert!(device.change_bitrate(crate::ChangeBitrate::B9600).is_ok());
 
     assert_eq!(
-        device.get_uart().segment_write_len,
-        crate::SEGMENT_WRITE_CHANGE_BIT_RATE
+        device.get_uart().segment_write.len(),
+        SEGMENT_WRITE_CHANGE_BIT_RATE
     );
     assert_eq!(
         device.get_uart().segment_write,
-        [0x00, 0x10, 0x00, 0x04, 0x00, 0x01, 0x02, 0x01, 0x06, 0x2b, 0xd6]
+        [0x01, 0x10, 0x00, 0x04, 0x00, 0x01, 0x02, 0x01, 0x06, 0x26, 0x46]
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_change_bitrate_method_keep_address() {
+    // Address 5, bitrate 4800
+    let register = with_crc(&[0x05, 0x03, 0x02, 0x05, 0x05]);
+    let acknowledge = with_crc(&[0x05, 0x10, 0x00, 0x04, 0x00, 0x01]);
+    let mut device = setup_builder(&[&register, &acknowledge])
+        .with_address(0x05)
+        .build()
+        .unwrap();
+
+    assert!(device.change_bitrate(crate::ChangeBitrate::B9600).is_ok());
+
+    assert_eq!(device.address(), 0x05);
+    assert_eq!(
+        device.get_uart().writes,
+        [
+            crate::modbus::Request::read_holding_registers(0x05, 0x0004, 1)
+                .as_bytes()
+                .to_vec(),
+            crate::modbus::Request::write_multiple_registers(0x05, 0x0004, &[0x0506])
+                .unwrap()
+                .as_bytes()
+                .to_vec()
+        ]
     );
 }
 
@@ -245,18 +356,14 @@ fn test_jsk_mk_196_read_method_return_error_cause_read_to_device_return_bad_crc(
     let mut device = setup(READ_DATA_BAD_CRC, WRITE_DATA_OK);
 
     match device.read() {
-        Ok(()) => panic!(),
-        Err(e) => assert_eq!(e.kind, crate::error::UartErrorKind::BadCrc),
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::BadCrc),
     };
 }
 
 #[test]
 fn test_jsk_mk_196_read_method_return_ok_when_read_to_device_return_bad_crc() {
-    let uart = UartTestImpl {
-        segment_write: WRITE_DATA_OK,
-        segment_read: READ_DATA_BAD_CRC,
-        segment_write_len: 0,
-    };
+    let uart = test_uart(&[&READ_DATA_BAD_CRC]);
 
     let delay = DelayTestImpl {};
 
@@ -266,3 +373,1320 @@ fn test_jsk_mk_196_read_method_return_ok_when_read_to_device_return_bad_crc() {
         panic!();
     }
 }
+
+#[test]
+fn test_modbus_read_holding_registers_request() {
+    let request = crate::modbus::Request::read_holding_registers(0x01, 0x0048, 0x000e);
+
+    assert_eq!(
+        request.as_bytes(),
+        [0x01, 0x03, 0x00, 0x48, 0x00, 0x0e, 0x44, 0x18]
+    );
+}
+
+#[test]
+fn test_modbus_write_multiple_registers_request() {
+    // Energy cleaning example of instruction book
+    let request =
+        crate::modbus::Request::write_multiple_registers(0x01, 0x000c, &[0x0000, 0x0000]).unwrap();
+
+    assert_eq!(
+        request.as_bytes(),
+        [0x01, 0x10, 0x00, 0x0c, 0x00, 0x02, 0x04, 0x00, 0x00, 0x00, 0x00, 0xf3, 0xfa]
+    );
+    assert!(crate::is_crc_ok(request.as_bytes()));
+}
+
+#[test]
+fn test_modbus_write_multiple_registers_request_with_wrong_size() {
+    assert!(crate::modbus::Request::write_multiple_registers(0x01, 0x000c, &[]).is_none());
+    assert!(crate::modbus::Request::write_multiple_registers(
+        0x01,
+        0x000c,
+        &[0; crate::modbus::MAX_WRITE_REGISTERS + 1]
+    )
+    .is_none());
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_send_request_to_configured_address() {
+    let mut device = setup_builder(&[&READ_DATA_OK])
+        .with_address(0x02)
+        .build()
+        .unwrap();
+
+    assert_eq!(device.address(), 0x02);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::WrongAddress),
+    };
+
+    let request = crate::modbus::Request::read_holding_registers(0x02, 0x0048, 0x000e);
+
+    assert_eq!(device.get_uart().segment_write, request.as_bytes());
+}
+
+#[test]
+fn test_jsk_mk_196_read_registers_method_return_values() {
+    // Model, voltage range, current range
+    let response = with_crc(&[0x01, 0x03, 0x06, 0x01, 0x94, 0x00, 0xfa, 0x03, 0x20]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    let registers = device.read_registers(0x0000, 3).unwrap();
+
+    assert_eq!(*registers, [0x0194, 250, 800]);
+    assert_eq!(
+        device.get_uart().segment_write,
+        crate::modbus::Request::read_holding_registers(0x01, 0x0000, 3).as_bytes()
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_read_registers_method_return_two_values_for_data_register() {
+    // Frequency register is 32 bits
+    let response = with_crc(&[0x01, 0x03, 0x04, 0x00, 0x00, 0x13, 0x8b]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    let registers = device.read_registers(0x004f, 1).unwrap();
+
+    assert_eq!(*registers, [0x0000, 0x138b]);
+}
+
+#[test]
+fn test_jsk_mk_196_read_registers_method_return_error_cause_response_size() {
+    // Module answers only one register
+    let response = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x94]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.read_registers(0x0000, 3) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::WrongByteCount),
+    };
+
+    // Module stops in middle of frame
+    let response = with_crc(&[0x01, 0x03, 0x06, 0x01, 0x94, 0x00, 0xfa, 0x03, 0x20]);
+    let mut device = setup_with_responses(&[&response[0..6]], WRITE_DATA_OK);
+
+    match device.read_registers(0x0000, 3) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::ReadInsuffisantBytes),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_registers_method_return_error_cause_too_many_registers() {
+    let mut device = setup(READ_DATA_OK, WRITE_DATA_OK);
+
+    match device.read_registers(0x0048, 16) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::InvalidRequest),
+    };
+
+    match device.read_registers(0x0000, 0) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::InvalidRequest),
+    };
+
+    assert!(device.get_uart().writes.is_empty());
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_return_ok() {
+    let response = with_crc(&[0x01, 0x10, 0x00, 0x0c, 0x00, 0x02]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    assert!(device.write_registers(0x000c, &[0x0000, 0x0000]).is_ok());
+    assert_eq!(
+        device.get_uart().segment_write,
+        [0x01, 0x10, 0x00, 0x0c, 0x00, 0x02, 0x04, 0x00, 0x00, 0x00, 0x00, 0xf3, 0xfa]
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_return_error_cause_acknowledge_mismatch() {
+    // Module acknowledge only one register
+    let response = with_crc(&[0x01, 0x10, 0x00, 0x0c, 0x00, 0x01]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.write_registers(0x000c, &[0x0000, 0x0000]) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::WriteRefused),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_return_error_cause_bad_crc() {
+    let response = [0x01, 0x10, 0x00, 0x0c, 0x00, 0x02, 0x00, 0x00];
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.write_registers(0x000c, &[0x0000, 0x0000]) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::BadCrc),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_return_error_cause_no_value() {
+    let mut device = setup(READ_DATA_OK, WRITE_DATA_OK);
+
+    match device.write_registers(0x000c, &[]) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::InvalidRequest),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_return_error_cause_exception() {
+    let response = with_crc(&[0x01, 0x83, 0x02]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(
+            *e.kind(),
+            crate::error::UartErrorKind::Exception(
+                crate::modbus::ExceptionCode::IllegalDataAddress
+            )
+        ),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_return_error_cause_exception() {
+    let response = with_crc(&[0x01, 0x90, 0x83]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.write_registers(0x0004, &[0xffff]) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(
+            *e.kind(),
+            crate::error::UartErrorKind::Exception(crate::modbus::ExceptionCode::IllegalDataValue)
+        ),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_return_error_cause_exception_with_bad_crc() {
+    let response = [0x01, 0x83, 0x02, 0x00, 0x00];
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::BadCrc),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_return_error_cause_wrong_function() {
+    let mut response = READ_DATA_OK.to_vec();
+    response[1] = 0x04;
+    let response = with_crc(&response[0..READ_DATA_SIZE - 2]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::WrongFunction),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_return_error_cause_wrong_byte_count() {
+    // Frame with two bytes less
+    let mut response = READ_DATA_OK.to_vec();
+    response[2] = 0x36;
+    let response = with_crc(&response[0..READ_DATA_SIZE - 4]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::WrongByteCount),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_return_error_cause_wrong_function() {
+    // Response of write single register
+    let response = with_crc(&[0x01, 0x06, 0x00, 0x0c, 0x00, 0x02]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    match device.write_registers(0x000c, &[0x0000, 0x0000]) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::WrongFunction),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_change_address_method_keep_bitrate() {
+    // Address 1, bitrate 9600
+    let register = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x06]);
+    let acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x04, 0x00, 0x01]);
+    let mut device = setup_with_responses(&[&register, &acknowledge], WRITE_DATA_OK);
+
+    assert!(device.change_address(0x05).is_ok());
+
+    assert_eq!(device.address(), 0x05);
+    assert_eq!(
+        device.get_uart().segment_write,
+        crate::modbus::Request::write_multiple_registers(0x01, 0x0004, &[0x0506])
+            .unwrap()
+            .as_bytes()
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_change_address_and_bitrate_method() {
+    let acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x04, 0x00, 0x01]);
+    let mut device = setup_with_responses(&[&acknowledge], WRITE_DATA_OK);
+
+    assert!(device
+        .change_address_and_bitrate(0x02, crate::ChangeBitrate::B19200)
+        .is_ok());
+
+    assert_eq!(device.address(), 0x02);
+    assert_eq!(
+        device.get_uart().segment_write,
+        crate::modbus::Request::write_multiple_registers(0x01, 0x0004, &[0x0207])
+            .unwrap()
+            .as_bytes()
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_change_address_method_return_error_cause_invalid_address() {
+    let mut device = setup(READ_DATA_OK, WRITE_DATA_OK);
+
+    for address in [0x00, 0xf8] {
+        match device.change_address(address) {
+            Ok(_) => panic!(),
+            Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::InvalidRequest),
+        };
+    }
+
+    assert_eq!(device.address(), 0x01);
+    assert!(device.get_uart().writes.is_empty());
+}
+
+#[test]
+fn test_jsk_mk_196_change_address_method_keep_address_when_write_refused() {
+    let register = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x05]);
+    let exception = with_crc(&[0x01, 0x90, 0x03]);
+    let mut device = setup_with_responses(&[&register, &exception], WRITE_DATA_OK);
+
+    assert!(device.change_address(0x05).is_err());
+    assert_eq!(device.address(), 0x01);
+}
+
+#[test]
+fn test_jsk_mk_196_reset_energy_method_return_ok() {
+    let acknowledge = [0x01, 0x10, 0x00, 0x0c, 0x00, 0x02, 0x81, 0xcb];
+    let mut device = setup_with_responses(&[&acknowledge], WRITE_DATA_OK);
+
+    assert!(device.reset_energy().is_ok());
+    assert_eq!(
+        device.get_uart().segment_write,
+        [0x01, 0x10, 0x00, 0x0c, 0x00, 0x02, 0x04, 0x00, 0x00, 0x00, 0x00, 0xf3, 0xfa]
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_reset_energy2_method_return_ok() {
+    let positive_acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x53, 0x00, 0x01]);
+    let negative_acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x55, 0x00, 0x01]);
+    let mut device = setup_with_responses(
+        &[&positive_acknowledge, &negative_acknowledge],
+        WRITE_DATA_OK,
+    );
+
+    assert!(device.reset_energy2().is_ok());
+    assert_eq!(
+        device.get_uart().writes,
+        [
+            with_crc(&[0x01, 0x10, 0x00, 0x53, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00]),
+            with_crc(&[0x01, 0x10, 0x00, 0x55, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00])
+        ]
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_reset_energy1_method_return_error_cause_exception() {
+    let exception = with_crc(&[0x01, 0x90, 0x02]);
+    let mut device = setup_with_responses(&[&exception], WRITE_DATA_OK);
+
+    match device.reset_energy1() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(
+            *e.kind(),
+            crate::error::UartErrorKind::Exception(
+                crate::modbus::ExceptionCode::IllegalDataAddress
+            )
+        ),
+    };
+    assert_eq!(device.get_uart().writes.len(), 1);
+    assert_eq!(device.get_uart().writes[0][2..6], [0x00, 0x4b, 0x00, 0x01]);
+}
+
+#[test]
+fn test_register_count() {
+    assert_eq!(crate::register_count(0x000c, 2), Some(2));
+    assert_eq!(crate::register_count(0x004b, 2), Some(1));
+    assert_eq!(crate::register_count(0x0046, 4), Some(3));
+    assert_eq!(crate::register_count(0x004b, 3), None);
+}
+
+#[test]
+fn test_jsk_mk_196_device_info_method_return_info() {
+    let response = with_crc(&[
+        0x01, 0x03, 0x0a, 0x01, 0x94, 0x00, 0x00, 0x00, 0xfa, 0x03, 0x20, 0x01, 0x05,
+    ]);
+    let mut device = setup_with_responses(&[&response], WRITE_DATA_OK);
+
+    let info = device.device_info().unwrap();
+
+    assert_eq!(
+        device.get_uart().segment_write,
+        [0x01, 0x03, 0x00, 0x00, 0x00, 0x05, 0x85, 0xc9]
+    );
+    assert_eq!(info.model(), 0x0194);
+    assert!(info.is_jsy_mk_194());
+    assert_eq!(info.voltage_range(), 250);
+    assert_eq!(info.current_range(), 80.0);
+    assert_eq!(info.address(), 0x01);
+    assert_eq!(info.bitrate_code(), 0x05);
+    assert_eq!(info.bitrate(), Some(4800));
+}
+
+#[test]
+fn test_jsk_mk_196_detect_baudrate_method_return_bitrate() {
+    let register = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x07]);
+    let mut device = setup_with_responses(
+        &[&READ_DATA_BAD_CRC, &READ_DATA_BAD_CRC, &register],
+        WRITE_DATA_OK,
+    );
+
+    assert_eq!(
+        device.detect_baudrate(50).unwrap(),
+        crate::ChangeBitrate::B19200
+    );
+    assert_eq!(device.get_uart().baudrate, 19200);
+}
+
+#[test]
+fn test_jsk_mk_196_detect_baudrate_method_return_error_cause_no_answer() {
+    let mut device = setup_builder(&[]).with_baudrate(9600).build().unwrap();
+
+    match device.detect_baudrate(50) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::BitrateNotDetected),
+    };
+    // One probe for each bitrate
+    assert_eq!(device.get_uart().writes.len(), 4);
+    assert_eq!(device.baudrate(), 9600);
+    assert_eq!(device.get_uart().baudrate, 9600);
+}
+
+#[test]
+fn test_jsk_mk_196_detect_baudrate_method_return_error_cause_uart_error() {
+    let mut device = setup_builder(&[&READ_DATA_BAD_CRC, &READ_DATA_ERROR])
+        .with_baudrate(9600)
+        .build()
+        .unwrap();
+
+    match device.detect_baudrate(50) {
+        Ok(_) => panic!(),
+        Err(e) => {
+            assert!(e.is_transport());
+            assert_eq!(*e.kind(), crate::error::UartErrorKind::Read);
+        }
+    };
+    // Probe stops at second bitrate
+    assert_eq!(device.get_uart().writes.len(), 2);
+    assert_eq!(device.baudrate(), 9600);
+    assert_eq!(device.get_uart().baudrate, 9600);
+}
+
+#[test]
+fn test_jsk_mk_196_switch_bitrate_method_update_uart() {
+    let acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x04, 0x00, 0x01]);
+    let register = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x08]);
+    let mut device = setup_with_responses(&[&acknowledge, &register], WRITE_DATA_OK);
+
+    assert!(device.switch_bitrate(crate::ChangeBitrate::B38400).is_ok());
+
+    assert_eq!(device.baudrate(), 38400);
+    assert_eq!(device.get_uart().baudrate, 38400);
+    assert_eq!(
+        device.get_uart().writes[0],
+        crate::modbus::Request::write_multiple_registers(0x01, 0x0004, &[0x0108])
+            .unwrap()
+            .as_bytes()
+    );
+}
+
+#[test]
+fn test_jsk_mk_196_switch_bitrate_method_rollback_uart_when_module_not_answer() {
+    let acknowledge = with_crc(&[0x01, 0x10, 0x00, 0x04, 0x00, 0x01]);
+    let mut device = setup_with_responses(&[&acknowledge, &READ_DATA_ERROR], WRITE_DATA_OK);
+
+    match device.switch_bitrate(crate::ChangeBitrate::B9600) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(
+            *e.parent.kind(),
+            crate::error::UartErrorKind::BitrateNotVerified
+        ),
+    };
+
+    assert_eq!(device.baudrate(), 4800);
+    assert_eq!(device.get_uart().baudrate, 4800);
+}
+
+#[test]
+fn test_jsk_mk_196_switch_bitrate_method_keep_uart_when_module_refuse() {
+    let exception = with_crc(&[0x01, 0x90, 0x03]);
+    let mut device = setup_with_responses(&[&exception], WRITE_DATA_OK);
+
+    match device.switch_bitrate(crate::ChangeBitrate::B9600) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(
+            *e.parent.kind(),
+            crate::error::UartErrorKind::Exception(crate::modbus::ExceptionCode::IllegalDataValue)
+        ),
+    };
+
+    assert_eq!(device.baudrate(), 4800);
+    assert_eq!(device.get_uart().writes.len(), 1);
+}
+
+#[test]
+fn test_jsy_bus_poll_method_return_result_of_each_device() {
+    let mut response_2 = READ_DATA_OK_2.to_vec();
+    response_2[0] = 0x02;
+    let response_2 = with_crc(&response_2[0..READ_DATA_SIZE - 2]);
+
+    let uart = test_uart(&[&READ_DATA_OK, &response_2, &READ_DATA_ERROR]);
+
+    let mut bus = crate::bus::JsyBus::new(uart, DelayTestImpl {}, [0x01, 0x02, 0x03]).unwrap();
+
+    let results = bus.poll();
+
+    assert!(results[0].is_ok());
+    assert!(results[1].is_ok());
+    assert_eq!(
+        *results[2].as_ref().unwrap_err().kind(),
+        crate::error::UartErrorKind::Read
+    );
+
+    let writes = &bus.get_uart().writes;
+    assert_eq!(writes.len(), 3);
+    assert_eq!(writes[0][0], 0x01);
+    assert_eq!(writes[1][0], 0x02);
+    assert_eq!(writes[2][0], 0x03);
+
+    assert_eq!(results[0].as_ref().unwrap().channel1().voltage(), 239.574_3);
+    let measurement = bus.device(0x02).unwrap().last_measurement().unwrap();
+    assert_eq!(measurement.channel1().current(), 0.1021);
+    assert_eq!(measurement.frequency(), 50.01);
+    assert!(bus.device(0x03).unwrap().last_measurement().is_none());
+    assert!(bus.device(0x04).is_none());
+}
+
+fn setup_bus(responses: &[&[u8]]) -> crate::bus::JsyBus<UartTestImpl, DelayTestImpl, 1> {
+    crate::bus::JsyBus::new(test_uart(responses), DelayTestImpl {}, [0x01]).unwrap()
+}
+
+#[test]
+fn test_jsy_bus_discover_method_return_modules_found() {
+    let model_2 = with_crc(&[0x02, 0x03, 0x02, 0x01, 0x94]);
+    let other_model_3 = with_crc(&[0x03, 0x03, 0x02, 0x02, 0x26]);
+    let model_4 = with_crc(&[0x04, 0x03, 0x02, 0x01, 0x94]);
+    let mut bus = setup_bus(&[&READ_DATA_BAD_CRC, &model_2, &other_model_3, &model_4]);
+    let mut found = [crate::bus::Discovered {
+        address: 0,
+        bitrate: 0,
+    }; 2];
+
+    assert_eq!(bus.discover(&mut found, 10).unwrap(), 2);
+
+    assert_eq!(
+        found,
+        [
+            crate::bus::Discovered {
+                address: 0x02,
+                bitrate: 4800
+            },
+            crate::bus::Discovered {
+                address: 0x04,
+                bitrate: 4800
+            }
+        ]
+    );
+    assert_eq!(
+        bus.get_uart().writes[0],
+        crate::modbus::Request::read_holding_registers(0x01, 0x0000, 1).as_bytes()
+    );
+    assert_eq!(bus.get_uart().writes.len(), 4);
+}
+
+#[test]
+fn test_jsy_bus_discover_all_bitrates_method_restore_uart() {
+    let mut bus = setup_bus(&[]);
+    let mut found = [crate::bus::Discovered {
+        address: 0,
+        bitrate: 0,
+    }; 2];
+
+    bus.change_baudrate(9600).unwrap();
+
+    assert_eq!(bus.discover_all_bitrates(&mut found, 10).unwrap(), 0);
+    assert_eq!(bus.get_uart().writes.len(), 247 * 4);
+    assert_eq!(bus.baudrate(), 9600);
+    assert_eq!(bus.get_uart().baudrate, 9600);
+}
+
+#[test]
+fn test_jsy_bus_discover_method_return_error_cause_write_error() {
+    let mut bus = setup_bus(&[]);
+    let mut found = [crate::bus::Discovered {
+        address: 0,
+        bitrate: 0,
+    }; 2];
+
+    bus.uart_mut().write_error = true;
+
+    match bus.discover(&mut found, 10) {
+        Ok(_) => panic!(),
+        Err(e) => {
+            assert!(e.parent.is_transport());
+            assert_eq!(*e.parent.kind(), crate::error::UartErrorKind::Write);
+            assert_eq!(e.found, 0);
+        }
+    };
+}
+
+#[test]
+fn test_jsy_bus_discover_all_bitrates_method_restore_uart_when_error() {
+    let model_1 = with_crc(&[0x01, 0x03, 0x02, 0x01, 0x94]);
+    let mut bus = setup_bus(&[&model_1, &READ_DATA_ERROR]);
+    let mut found = [crate::bus::Discovered {
+        address: 0,
+        bitrate: 0,
+    }; 2];
+
+    bus.change_baudrate(9600).unwrap();
+
+    match bus.discover_all_bitrates(&mut found, 10) {
+        Ok(_) => panic!(),
+        Err(e) => {
+            assert_eq!(*e.parent.kind(), crate::error::UartErrorKind::Read);
+            assert_eq!(e.found, 1);
+        }
+    };
+    assert_eq!(
+        found[0],
+        crate::bus::Discovered {
+            address: 0x01,
+            bitrate: 4800
+        }
+    );
+    assert_eq!(bus.get_uart().writes.len(), 2);
+    assert_eq!(bus.baudrate(), 9600);
+    assert_eq!(bus.get_uart().baudrate, 9600);
+}
+
+#[test]
+fn test_jsy_bus_builder_build_method_return_configured_bus() {
+    let mut bus = crate::bus::JsyBusBuilder::new(
+        test_uart(&[&READ_DATA_ERROR, &READ_DATA_BAD_CRC]),
+        DelayTestImpl {},
+        [0x01],
+    )
+    .with_crc_check(false)
+    .with_timeout_ms(500)
+    .with_retry_policy(crate::retry::RetryPolicy::new(2, 0))
+    .with_baudrate(9600)
+    .build()
+    .unwrap();
+
+    assert_eq!(bus.baudrate(), 9600);
+    assert_eq!(bus.timeout_ms(), 500);
+    assert!(bus.poll()[0].is_ok());
+    assert_eq!(bus.get_uart().writes.len(), 2);
+}
+
+#[test]
+fn test_jsy_bus_builder_build_method_return_error_cause_invalid_config() {
+    let builders = [
+        crate::bus::JsyBusBuilder::new(test_uart(&[]), DelayTestImpl {}, [0x01, 0x00]),
+        crate::bus::JsyBusBuilder::new(test_uart(&[]), DelayTestImpl {}, [0x01, 0xf8]),
+        crate::bus::JsyBusBuilder::new(test_uart(&[]), DelayTestImpl {}, [0x01, 0x02])
+            .with_timeout_ms(0),
+        crate::bus::JsyBusBuilder::new(test_uart(&[]), DelayTestImpl {}, [0x01, 0x02])
+            .with_baudrate(115200),
+    ];
+
+    for builder in builders {
+        match builder.build() {
+            Ok(_) => panic!(),
+            Err(e) => assert_eq!(e.parent.kind, crate::error::UartErrorKind::InvalidConfig),
+        };
+    }
+}
+
+#[test]
+fn test_jsy_bus_new_method_return_uart_when_invalid_address() {
+    let error = match crate::bus::JsyBus::new(test_uart(&[]), DelayTestImpl {}, [0x01, 0xff]) {
+        Ok(_) => panic!(),
+        Err(e) => e,
+    };
+
+    assert_eq!(error.parent.detail, crate::error::ErrorDetail::Slave(0xff));
+    assert_eq!(error.builder.release().0.baudrate, 4800);
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_accumulate_partial_reads() {
+    let mut device = setup_with_responses(
+        &[
+            &READ_DATA_OK[0..2],
+            &READ_DATA_OK[2..32],
+            &READ_DATA_OK[32..],
+        ],
+        WRITE_DATA_OK,
+    );
+
+    let measurement = device.read().unwrap();
+    assert_eq!(measurement.channel2().power(), -908.155_9);
+    assert_eq!(device.get_uart().read_count, 3);
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_stop_at_size_of_exception() {
+    let response = with_crc(&[0x01, 0x83, 0x02]);
+    let mut device = setup_with_responses(&[&response[0..3], &response[3..]], WRITE_DATA_OK);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(
+            *e.kind(),
+            crate::error::UartErrorKind::Exception(
+                crate::modbus::ExceptionCode::IllegalDataAddress
+            )
+        ),
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_return_error_cause_timeout_in_frame() {
+    let mut device = setup_with_responses(&[&READ_DATA_OK[0..32]], WRITE_DATA_OK);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => {
+            assert_eq!(*e.kind(), crate::error::UartErrorKind::ReadInsuffisantBytes);
+            assert_eq!(
+                e.error().detail,
+                crate::error::ErrorDetail::Size {
+                    expected: 61,
+                    actual: 32
+                }
+            );
+        }
+    };
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_read_with_time_left() {
+    let mut device = setup_builder(&[&READ_DATA_OK[0..32], &READ_DATA_OK[32..]])
+        .with_baudrate(9600)
+        .build()
+        .unwrap();
+
+    assert!(device.read().is_ok());
+
+    // 100ms and 69.9ms to transmit 61 bytes, then 36.7ms for 32 bytes received
+    assert_eq!(device.get_uart().timeouts, [170, 134]);
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_stop_when_time_left_is_spent() {
+    let chunks: Vec<&[u8]> = READ_DATA_OK.chunks(1).collect();
+    let mut device = setup_with_responses(&chunks, WRITE_DATA_OK);
+
+    assert!(device.read_with_timeout(0).is_ok());
+
+    // 61 bytes take 139.8ms at 4800 bit/s
+    let timeouts = &device.get_uart().timeouts;
+    assert_eq!(timeouts[0], 140);
+    assert_eq!(timeouts[60], 3);
+    assert!(timeouts.windows(2).all(|t| t[0] >= t[1]));
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_clear_late_bytes() {
+    // Late bytes after first response
+    let mut first_response = READ_DATA_OK.to_vec();
+    first_response.extend_from_slice(&[0x01, 0x03, 0x38]);
+    let mut device = setup_with_responses(&[&first_response, &READ_DATA_OK_2], WRITE_DATA_OK);
+
+    assert!(device.read().is_ok());
+    assert_eq!(device.read().unwrap().frequency(), 50.01);
+}
+
+/// UART without `clear_input`, to check default implementation
+struct FifoUartTestImpl {
+    /// Bytes received, `None` for a bus never silent
+    pub fifo: Option<Vec<u8>>,
+    pub read_count: usize,
+}
+
+impl crate::Uart for FifoUartTestImpl {
+    type Error = UartTestError;
+
+    fn read(&mut self, buf: &mut [u8], _timeout: u32) -> Result<usize, UartTestError> {
+        self.read_count += 1;
+
+        let Some(fifo) = self.fifo.as_mut() else {
+            return Ok(buf.len());
+        };
+
+        let size = buf.len().min(fifo.len());
+        fifo.drain(0..size);
+
+        Ok(size)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, UartTestError> {
+        Ok(bytes.len())
+    }
+
+    fn change_baudrate(&mut self, _f: u32) -> Result<(), UartTestError> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_uart_clear_input_method_drop_received_bytes() {
+    let mut uart = FifoUartTestImpl {
+        fifo: Some(vec![0x01; 40]),
+        read_count: 0,
+    };
+
+    assert!(crate::Uart::clear_input(&mut uart).is_ok());
+    assert_eq!(uart.fifo, Some(Vec::new()));
+    assert_eq!(uart.read_count, 4);
+}
+
+#[test]
+fn test_uart_clear_input_method_stop_when_bus_never_silent() {
+    let mut uart = FifoUartTestImpl {
+        fifo: None,
+        read_count: 0,
+    };
+
+    assert!(crate::Uart::clear_input(&mut uart).is_ok());
+    assert_eq!(uart.read_count, crate::CLEAR_INPUT_MAX_READ);
+}
+
+#[test]
+fn test_silent_interval() {
+    assert_eq!(crate::link::silent_interval_us(4800), 8020);
+    assert_eq!(crate::link::silent_interval_us(9600), 4010);
+    assert_eq!(crate::link::silent_interval_us(19200), 2005);
+    assert_eq!(crate::link::silent_interval_us(38400), 1750);
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_retry_after_bad_crc() {
+    let mut device = setup_with_responses(&[&READ_DATA_BAD_CRC, &READ_DATA_OK], WRITE_DATA_OK)
+        .with_retry_policy(crate::retry::RetryPolicy::new(3, 10));
+
+    let measurement = device.read().unwrap();
+    assert_eq!(device.get_uart().writes.len(), 2);
+    assert_eq!(measurement.frequency(), 50.03);
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_return_error_with_attempts() {
+    let mut device = setup_with_responses(
+        &[&READ_DATA_BAD_CRC, &READ_DATA_BAD_CRC, &READ_DATA_BAD_CRC],
+        WRITE_DATA_OK,
+    )
+    .with_retry_policy(crate::retry::RetryPolicy::new(3, 10));
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => {
+            assert_eq!(*e.kind(), crate::error::UartErrorKind::BadCrc);
+            assert_eq!(e.attempts(), 3);
+        }
+    };
+    assert_eq!(device.get_uart().writes.len(), 3);
+}
+
+#[test]
+fn test_jsk_mk_196_write_registers_method_not_retry_exception() {
+    let response = with_crc(&[0x01, 0x90, 0x83]);
+    let mut device = setup_with_responses(&[&response, &WRITE_DATA_OK], WRITE_DATA_OK)
+        .with_retry_policy(crate::retry::RetryPolicy::new(3, 10));
+
+    match device.write_registers(0x0004, &[0xffff]) {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(e.attempts(), 1),
+    };
+    assert_eq!(device.get_uart().writes.len(), 1);
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_retry_with_custom_check() {
+    let response = with_crc(&[0x01, 0x83, 0x02]);
+    let mut device = setup_with_responses(&[&response, &READ_DATA_OK], WRITE_DATA_OK)
+        .with_retry_policy(
+            crate::retry::RetryPolicy::new(2, 0)
+                .with_retryable(|kind| matches!(kind, crate::error::UartErrorKind::Exception(_))),
+        );
+
+    assert!(device.read().is_ok());
+}
+
+fn setup_builder(
+    responses: &[&[u8]],
+) -> crate::builder::JsyMk194Builder<UartTestImpl, DelayTestImpl> {
+    crate::builder::JsyMk194Builder::new(test_uart(responses), DelayTestImpl {})
+}
+
+#[test]
+fn test_builder_build_method_return_configured_device() {
+    let device = setup_builder(&[])
+        .with_address(0x02)
+        .with_timeout_ms(500)
+        .with_retry_policy(crate::retry::RetryPolicy::new(3, 10))
+        .with_baudrate(9600)
+        .build()
+        .unwrap();
+
+    assert_eq!(device.address(), 0x02);
+    assert_eq!(device.timeout_ms(), 500);
+    assert_eq!(device.retry_policy().max_attempts(), 3);
+    assert_eq!(device.baudrate(), 9600);
+}
+
+#[test]
+fn test_builder_build_method_return_device_without_crc_check() {
+    let mut device = setup_builder(&[&READ_DATA_BAD_CRC])
+        .with_crc_check(false)
+        .build()
+        .unwrap();
+
+    assert!(device.read().is_ok());
+}
+
+#[test]
+fn test_builder_build_method_return_device_with_calibration() {
+    let mut device = setup_builder(&[&READ_DATA_OK])
+        .with_calibration1(crate::Calibration::new(2.0, 1.0))
+        .build()
+        .unwrap();
+
+    let measurement = device.read().unwrap();
+    assert_eq!(measurement.channel1().voltage(), 239.574_3 * 2.0);
+    assert_eq!(measurement.channel1().current(), 3.789_899_8);
+    assert_eq!(measurement.channel1().power(), -909.431_4 * 2.0);
+    assert_eq!(measurement.channel1().positive_energy(), 1.17 * 2.0);
+    assert_eq!(measurement.channel2().voltage(), 239.574_3);
+}
+
+#[test]
+fn test_builder_build_method_return_error_cause_invalid_config() {
+    let builders = [
+        setup_builder(&[]).with_address(0),
+        setup_builder(&[]).with_timeout_ms(0),
+        setup_builder(&[]).with_baudrate(115200),
+        setup_builder(&[]).with_calibration2(crate::Calibration::new(1.0, f32::NAN)),
+    ];
+
+    for builder in builders {
+        match builder.build() {
+            Ok(_) => panic!(),
+            Err(e) => assert_eq!(e.parent.kind, crate::error::UartErrorKind::InvalidConfig),
+        };
+    }
+}
+
+#[test]
+fn test_builder_build_method_return_uart_when_invalid_config() {
+    let error = match setup_builder(&[&READ_DATA_OK]).with_address(0).build() {
+        Ok(_) => panic!(),
+        Err(e) => e,
+    };
+
+    assert_eq!(error.parent.detail, crate::error::ErrorDetail::Slave(0x00));
+
+    let (uart, delay) = error.builder.release();
+    let mut device = crate::JsyMk194::new(uart, delay);
+
+    assert!(device.read().is_ok());
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_keep_last_measurement_when_error() {
+    let mut device = setup_with_responses(&[&READ_DATA_OK, &READ_DATA_BAD_CRC], WRITE_DATA_OK);
+
+    assert!(device.last_measurement().is_none());
+
+    let measurement = device.read().unwrap();
+    assert_eq!(measurement.sequence(), 1);
+
+    assert!(device.read().is_err());
+    assert_eq!(device.last_measurement(), Some(measurement));
+}
+
+#[test]
+fn test_jsk_mk_196_read_method_increment_sequence() {
+    let mut device = setup_with_responses(&[&READ_DATA_OK, &READ_DATA_OK_2], WRITE_DATA_OK);
+
+    let first = device.read().unwrap();
+    let second = device.read().unwrap();
+
+    assert_eq!(second.sequence(), first.sequence() + 1);
+    assert_eq!(first.frequency(), 50.03);
+    assert_eq!(second.frequency(), 50.01);
+}
+
+#[test]
+fn test_frame_parse_method_return_measurement() {
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK).unwrap();
+
+    assert_eq!(measurement.sequence(), 0);
+    assert_eq!(measurement.channel1().voltage(), 239.574_3);
+    assert_eq!(measurement.channel1().power(), -909.431_4);
+    assert_eq!(measurement.channel2().power(), -908.155_9);
+    assert_eq!(measurement.frequency(), 50.03);
+}
+
+#[test]
+fn test_frame_new_method_return_frame() {
+    let frame = crate::frame::Frame::new(&READ_DATA_OK_2).unwrap();
+
+    assert_eq!(frame.address(), 0x01);
+    assert_eq!(frame.as_bytes().len(), crate::frame::FRAME_SIZE);
+    assert_eq!(frame.measurement().frequency(), 50.01);
+}
+
+#[test]
+fn test_frame_parse_method_return_error() {
+    let mut wrong_function = READ_DATA_OK.to_vec();
+    wrong_function[1] = 0x04;
+    let wrong_function = with_crc(&wrong_function[0..READ_DATA_SIZE - 2]);
+
+    let mut wrong_byte_count = READ_DATA_OK.to_vec();
+    wrong_byte_count[2] = 0x36;
+    let wrong_byte_count = with_crc(&wrong_byte_count[0..READ_DATA_SIZE - 2]);
+
+    let exception = with_crc(&[0x01, 0x83, 0x02]);
+
+    assert_eq!(
+        crate::frame::Frame::parse(&READ_DATA_OK[0..32]),
+        Err(crate::error::ParseError::WrongSize(32))
+    );
+    assert_eq!(
+        crate::frame::Frame::parse(&READ_DATA_BAD_CRC),
+        Err(crate::error::ParseError::BadCrc)
+    );
+    assert_eq!(
+        crate::frame::Frame::parse(&wrong_function),
+        Err(crate::error::ParseError::WrongFunction(0x04))
+    );
+    assert_eq!(
+        crate::frame::Frame::parse(&wrong_byte_count),
+        Err(crate::error::ParseError::WrongByteCount(0x36))
+    );
+    assert_eq!(
+        crate::frame::Frame::parse(&exception),
+        Err(crate::error::ParseError::Exception(
+            crate::modbus::ExceptionCode::IllegalDataAddress
+        ))
+    );
+}
+
+#[test]
+fn test_channel_raw_values() {
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK).unwrap();
+    let channel1 = measurement.channel1();
+
+    assert_eq!(channel1.voltage_raw(), 2_395_743);
+    assert_eq!(channel1.current_raw(), 37_899);
+    assert_eq!(channel1.power_raw(), -9_094_314);
+    assert_eq!(channel1.positive_energy_raw(), 11_700);
+    assert_eq!(channel1.negative_energy_raw(), 9_750);
+    assert_eq!(channel1.factor_raw(), 1_000);
+    assert_eq!(measurement.frequency_raw(), 5_003);
+}
+
+#[test]
+fn test_channel_f64_values_keep_resolution() {
+    // Positive energy of channel 1 is 9999.9999 kWh
+    let mut response = READ_DATA_OK.to_vec();
+    response[15..19].copy_from_slice(&[0x05, 0xF5, 0xE0, 0xFF]);
+    let response = with_crc(&response[0..READ_DATA_SIZE - 2]);
+
+    let measurement = crate::frame::Frame::parse(&response).unwrap();
+    let channel1 = measurement.channel1();
+
+    assert_eq!(channel1.positive_energy_raw(), 99_999_999);
+    assert_eq!(channel1.positive_energy(), 10_000.0);
+    assert!((channel1.positive_energy_f64() - 9_999.999_9).abs() < 1e-9);
+    assert!((channel1.voltage_f64() - 239.574_3).abs() < 1e-9);
+    assert!((channel1.power_f64() + 909.431_4).abs() < 1e-9);
+    assert!((measurement.frequency_f64() - 50.03).abs() < 1e-9);
+}
+
+#[test]
+fn test_channel_direction() {
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK).unwrap();
+    let channel1 = measurement.channel1();
+
+    assert_eq!(channel1.direction(), crate::Direction::Export);
+    assert_eq!(channel1.signed_current(), -3.789_899_8);
+    assert_eq!(channel1.signed_factor(), -1.0);
+    assert_eq!(channel1.signed_current_raw(), -37_899);
+    assert_eq!(channel1.signed_factor_raw(), -1_000);
+
+    // Sign of channel 2 is 0, power of channel 2 is 0 W
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK_2).unwrap();
+    let channel2 = measurement.channel2();
+
+    assert_eq!(channel2.power(), 0.0);
+    assert_eq!(channel2.direction(), crate::Direction::Import);
+    assert_eq!(channel2.signed_current(), channel2.current());
+}
+
+#[test]
+fn test_channel_direction_at_zero_power() {
+    let mut response = READ_DATA_OK_2.to_vec();
+    response[28] = 0x01;
+    let response = with_crc(&response[0..READ_DATA_SIZE - 2]);
+
+    let measurement = crate::frame::Frame::parse(&response).unwrap();
+
+    assert_eq!(measurement.channel2().power(), 0.0);
+    assert_eq!(measurement.channel2().direction(), crate::Direction::Export);
+}
+
+#[test]
+fn test_channel_derived_values() {
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK_2).unwrap();
+    let channel1 = measurement.channel1();
+
+    // 234.6788 V, 0.1021 A, 13.9157 W on export, power factor 0.581
+    assert!((channel1.apparent_power_f64() - 23.960_705).abs() < 1e-6);
+    assert!((channel1.reactive_power_f64() - 19.505_607).abs() < 1e-6);
+    assert!((channel1.phase_angle_f64() - (180.0 - 54.479_092)).abs() < 1e-6);
+    assert_eq!(
+        channel1.apparent_power(),
+        channel1.apparent_power_f64() as f32
+    );
+}
+
+#[test]
+fn test_channel_derived_values_with_rounding() {
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK).unwrap();
+    let channel1 = measurement.channel1();
+
+    // Power factor 1.000 and power greater than voltage x current, on export
+    assert!(channel1.power_f64().abs() > channel1.apparent_power_f64());
+    assert_eq!(channel1.reactive_power(), 0.0);
+    assert_eq!(channel1.phase_angle(), 180.0);
+
+    let measurement = crate::frame::Frame::parse(&READ_DATA_OK_2).unwrap();
+    let channel2 = measurement.channel2();
+
+    // No load: power factor 0
+    assert_eq!(channel2.apparent_power(), 0.0);
+    assert_eq!(channel2.reactive_power(), 0.0);
+    assert_eq!(channel2.phase_angle(), 90.0);
+}
+
+#[test]
+fn test_uart_error_display_with_detail() {
+    let error = crate::error::UartError::from(crate::error::UartErrorKind::WrongAddress)
+        .with_detail(crate::error::ErrorDetail::Address {
+            expected: 1,
+            actual: 2,
+        });
+
+    assert_eq!(
+        error.to_string(),
+        "response comes from another module, expected address 1, got address 2"
+    );
+}
+
+#[test]
+fn test_jsy_error_display_and_source() {
+    let error: crate::error::JsyError<UartTestError> =
+        crate::error::JsyError::read(UartTestError::Read);
+
+    assert!(error.is_transport());
+    assert_eq!(error.to_string(), "read on UART failed: test error Read");
+    assert!(std::error::Error::source(&error).is_some());
+
+    let error: crate::error::JsyError<UartTestError> =
+        crate::error::UartError::from(crate::error::UartErrorKind::BadCrc).into();
+
+    assert!(!error.is_transport());
+    assert_eq!(error.to_string(), "CRC of response is not valid");
+    assert!(std::error::Error::source(&error).is_none());
+}
+
+/// Serial port answering each write with next response
+#[cfg(feature = "embedded-io")]
+struct SerialTestImpl {
+    responses: Vec<Vec<u8>>,
+    input: Vec<u8>,
+    writes: Vec<u8>,
+    baudrate: u32,
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ErrorType for SerialTestImpl {
+    type Error = embedded_io::ErrorKind;
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Read for SerialTestImpl {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, embedded_io::ErrorKind> {
+        let size = buf.len().min(self.input.len());
+
+        buf[0..size].copy_from_slice(&self.input[0..size]);
+        self.input.drain(0..size);
+
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::ReadReady for SerialTestImpl {
+    fn read_ready(&mut self) -> Result<bool, embedded_io::ErrorKind> {
+        Ok(!self.input.is_empty())
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl embedded_io::Write for SerialTestImpl {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, embedded_io::ErrorKind> {
+        self.writes.extend_from_slice(buf);
+
+        if !self.responses.is_empty() {
+            self.input = self.responses.remove(0);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), embedded_io::ErrorKind> {
+        Ok(())
+    }
+}
+
+/// Delay counting time waited
+#[cfg(feature = "embedded-io")]
+struct CountDelayTestImpl {
+    waited_ns: u64,
+}
+
+#[cfg(feature = "embedded-io")]
+impl DelayNs for CountDelayTestImpl {
+    fn delay_ns(&mut self, ns: u32) {
+        self.waited_ns += ns as u64;
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+#[test]
+fn test_serial_uart_read_method_wait_timeout() {
+    let serial = SerialTestImpl {
+        responses: Vec::new(),
+        input: Vec::new(),
+        writes: Vec::new(),
+        baudrate: 4800,
+    };
+    let mut uart = crate::serial::SerialUart::new(serial, CountDelayTestImpl { waited_ns: 0 });
+    let mut buf = [0; 8];
+
+    assert_eq!(crate::Uart::read(&mut uart, &mut buf, 240), Ok(0));
+    assert_eq!(uart.release().1.waited_ns, 240_000_000);
+}
+
+#[cfg(feature = "embedded-io")]
+fn setup_serial(
+    responses: &[&[u8]],
+) -> crate::JsyMk194<crate::serial::SerialUart<SerialTestImpl, DelayTestImpl>, DelayTestImpl> {
+    let serial = SerialTestImpl {
+        responses: responses.iter().map(|r| r.to_vec()).collect(),
+        input: Vec::new(),
+        writes: Vec::new(),
+        baudrate: 4800,
+    };
+
+    crate::JsyMk194::new(
+        crate::serial::SerialUart::new(serial, DelayTestImpl {}),
+        DelayTestImpl {},
+    )
+}
+
+#[cfg(feature = "embedded-io")]
+#[test]
+fn test_serial_uart_read_method_return_measurement() {
+    let mut device = setup_serial(&[&READ_DATA_OK]);
+
+    let measurement = device.read().unwrap();
+
+    assert_eq!(measurement.frequency(), 50.03);
+    assert_eq!(
+        device.get_uart().serial().writes,
+        crate::modbus::Request::read_holding_registers(0x01, 0x0048, 0x000e).as_bytes()
+    );
+}
+
+#[cfg(feature = "embedded-io")]
+#[test]
+fn test_serial_uart_read_method_return_error_cause_timeout() {
+    let mut device = setup_serial(&[]);
+
+    match device.read() {
+        Ok(_) => panic!(),
+        Err(e) => assert_eq!(*e.kind(), crate::error::UartErrorKind::ReadInsuffisantBytes),
+    };
+}
+
+#[cfg(feature = "embedded-io")]
+#[test]
+fn test_serial_uart_change_baudrate_method() {
+    let mut device = setup_serial(&[]);
+
+    match device.change_baudrate(9600) {
+        Ok(_) => panic!(),
+        Err(e) => assert!(matches!(
+            e,
+            crate::error::JsyError::Transport {
+                source: crate::serial::SerialError::ChangeBaudrateUnsupported,
+                ..
+            }
+        )),
+    };
+
+    let serial = SerialTestImpl {
+        responses: Vec::new(),
+        input: Vec::new(),
+        writes: Vec::new(),
+        baudrate: 4800,
+    };
+    let mut uart = crate::serial::SerialUart::new(serial, DelayTestImpl {}).with_change_baudrate(
+        |serial, f| {
+            serial.baudrate = f;
+            Ok(())
+        },
+    );
+
+    assert!(crate::Uart::change_baudrate(&mut uart, 9600).is_ok());
+    assert_eq!(uart.serial().baudrate, 9600);
+}